@@ -0,0 +1,59 @@
+//! Android logcat support.
+//!
+//! Outside of `adb shell`, the standard output and error of an Android process go nowhere.
+//! With the `android-log` feature, lines written to them are sent to logcat with
+//! `__android_log_write` instead: the standard output at `INFO` priority, and the standard
+//! error at `ERROR` priority.
+
+use core::ffi::{c_char, c_int, CStr};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+const ANDROID_LOG_INFO: c_int = 4;
+const ANDROID_LOG_ERROR: c_int = 6;
+
+#[link(name = "log")]
+extern "C" {
+    fn __android_log_write(prio: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+}
+
+static TAG: AtomicPtr<c_char> = AtomicPtr::new(b"libc-print\0".as_ptr() as *mut c_char);
+
+/// Sets the tag that output sent to logcat is logged under, returning the previous one.
+///
+/// The default tag is `libc-print`.
+///
+/// ```rust,no_run
+/// # use libc_print::{libc_println, set_android_log_tag};
+/// use core::ffi::CStr;
+///
+/// set_android_log_tag(CStr::from_bytes_with_nul(b"myapp\0").unwrap());
+/// libc_println!("started");
+/// //  ^-- logs: I myapp   : started
+/// ```
+pub fn set_android_log_tag(tag: &'static CStr) -> &'static CStr {
+    let previous = TAG.swap(tag.as_ptr() as *mut c_char, Ordering::Relaxed);
+    // SAFETY: every pointer stored in `TAG` comes from a `&'static CStr`
+    unsafe { CStr::from_ptr(previous) }
+}
+
+/// Sends `bytes` to logcat if `fd` is the standard output or error, returning `None` if the
+/// caller should write to the descriptor itself.
+///
+/// Logcat ends each entry itself, so a trailing newline is dropped.
+pub(crate) fn write_log(fd: i32, bytes: &[u8]) -> Option<Result<usize, i32>> {
+    let prio = match fd {
+        crate::__LIBC_STDOUT => ANDROID_LOG_INFO,
+        crate::__LIBC_STDERR => ANDROID_LOG_ERROR,
+        _ => return None,
+    };
+    let tag = TAG.load(Ordering::Relaxed);
+
+    let res = crate::logline::for_each_message(bytes, |msg| {
+        // SAFETY: `tag` and `msg` are NUL-terminated
+        match unsafe { __android_log_write(prio, tag, msg) } {
+            res if res < 0 => Err(-res),
+            _ => Ok(()),
+        }
+    });
+    Some(res.map(|()| bytes.len()))
+}
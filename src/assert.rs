@@ -0,0 +1,236 @@
+use core::fmt::{Arguments, Debug, Display, Formatter};
+use core::panic::Location;
+
+/// Asserts that a boolean expression is `true`, printing a message to the standard error and
+/// aborting the process if it isn't.
+///
+/// The message matches the one printed by [`assert!`](https://doc.rust-lang.org/std/macro.assert.html),
+/// but is written without relying on a panic handler, so it is useful in `#![no_std]`
+/// binaries that don't print anything when they panic.
+///
+/// ```rust
+/// # use libc_print::libc_assert;
+/// let len = 3;
+/// libc_assert!(len > 0);
+/// libc_assert!(len < 10, "length {} is too large", len);
+/// ```
+#[macro_export]
+macro_rules! libc_assert {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::__libc_assert_failed(format_args!(
+                "assertion failed: {}",
+                stringify!($cond)
+            ))
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::__libc_assert_failed(format_args!($($arg)+))
+        }
+    };
+}
+
+/// Asserts that two expressions are equal, printing both values to the standard error and
+/// aborting the process if they aren't.
+///
+/// See [`libc_assert`] for details.
+///
+/// ```rust
+/// # use libc_print::libc_assert_eq;
+/// libc_assert_eq!(1 + 1, 2);
+/// libc_assert_eq!("a", "a", "strings should match");
+/// ```
+#[macro_export]
+macro_rules! libc_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::__libc_assert_cmp_failed("==", &*left, &*right, None)
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::__libc_assert_cmp_failed(
+                        "==",
+                        &*left,
+                        &*right,
+                        Some(format_args!($($arg)+)),
+                    )
+                }
+            }
+        }
+    };
+}
+
+/// Asserts that two expressions are not equal, printing both values to the standard error and
+/// aborting the process if they are.
+///
+/// See [`libc_assert`] for details.
+///
+/// ```rust
+/// # use libc_print::libc_assert_ne;
+/// libc_assert_ne!(1 + 1, 3);
+/// ```
+#[macro_export]
+macro_rules! libc_assert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    $crate::__libc_assert_cmp_failed("!=", &*left, &*right, None)
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    $crate::__libc_assert_cmp_failed(
+                        "!=",
+                        &*left,
+                        &*right,
+                        Some(format_args!($($arg)+)),
+                    )
+                }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __libc_assert_failed(message: Arguments) -> ! {
+    fail(Location::caller(), message)
+}
+
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __libc_assert_cmp_failed(
+    op: &str,
+    left: &dyn Debug,
+    right: &dyn Debug,
+    message: Option<Arguments>,
+) -> ! {
+    fail(
+        Location::caller(),
+        CmpFailure {
+            op,
+            left,
+            right,
+            message,
+        },
+    )
+}
+
+fn fail(location: &Location, message: impl Display) -> ! {
+    {
+        let mut stm = crate::__LibCBufWriter::new(crate::__LIBC_STDERR);
+        let _ = crate::panic::write_panic(&mut stm, Some(location), message);
+    }
+    crate::sync_with_stdio();
+    crate::__libc_abort()
+}
+
+/// The message for a failed `libc_assert_eq!` or `libc_assert_ne!`.
+struct CmpFailure<'a> {
+    op: &'a str,
+    left: &'a dyn Debug,
+    right: &'a dyn Debug,
+    message: Option<Arguments<'a>>,
+}
+
+impl Display for CmpFailure<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "assertion `left {} right` failed", self.op)?;
+        if let Some(message) = self.message {
+            write!(f, ": {}", message)?;
+        }
+        let nl = crate::__LIBC_NEWLINE;
+        write!(f, "{}  left: {:?}{} right: {:?}", nl, self.left, nl, self.right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+
+    #[test]
+    fn test_cmp_failure_format() {
+        let failure = CmpFailure {
+            op: "==",
+            left: &1,
+            right: &"two",
+            message: None,
+        };
+        let nl = crate::__LIBC_NEWLINE;
+        assert_eq!(
+            failure.to_string(),
+            std::format!(
+                "assertion `left == right` failed{}  left: 1{} right: \"two\"",
+                nl,
+                nl
+            )
+        );
+
+        let mut s = String::new();
+        let failure = CmpFailure {
+            op: "!=",
+            left: &[1, 2],
+            right: &[1, 2],
+            message: Some(format_args!("see {}", "docs")),
+        };
+        core::fmt::write(&mut s, format_args!("{}", failure)).unwrap();
+        assert_eq!(
+            s,
+            std::format!(
+                "assertion `left != right` failed: see docs{}  left: [1, 2]{} right: [1, 2]",
+                nl,
+                nl
+            )
+        );
+    }
+
+    #[test]
+    fn test_passing_asserts() {
+        let v = [1, 2, 3];
+        libc_assert!(v.len() == 3);
+        libc_assert!(!v.is_empty(), "{:?} is empty", v);
+        libc_assert_eq!(v[0], 1);
+        libc_assert_eq!(v[1], 2, "second element");
+        libc_assert_ne!(v[0], v[2]);
+        libc_assert_ne!(v[0], v[2], "{} and {}", v[0], v[2]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_assert_aborts() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output =
+            crate::tests::run_in_child("assert::tests::test_failing_assert_aborts", || {
+                // With the `c-stdio` feature, this is only buffered until the assertion fails
+                crate::libc_print!("before the assertion");
+                libc_assert_eq!(1 + 1, 3, "math is broken");
+            });
+        assert_eq!(output.status.signal(), Some(libc::SIGABRT));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("before the assertion"));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let nl = crate::__LIBC_NEWLINE;
+        assert!(stderr.contains(&std::format!(
+            "assertion `left == right` failed: math is broken{}  left: 2{} right: 3{}",
+            nl,
+            nl,
+            nl
+        )));
+    }
+}
@@ -0,0 +1,215 @@
+use core::marker::PhantomData;
+
+/// The error a [`Backend`] returns when a write was interrupted before anything was written.
+/// The write is tried again.
+///
+/// This is `errno`'s `EINTR` everywhere except Windows and the `semihosting` feature, which have
+/// no such error, so use `-1` instead.
+pub const EINTR: i32 = crate::sys::EINTR;
+
+/// The error a [`Backend`] returns when the file descriptor can't take any more bytes yet.
+/// The write is tried again after [`Backend::wait_writable`], subject to
+/// [`set_would_block`](crate::set_would_block).
+///
+/// This is `errno`'s `EAGAIN` on Unix and WASI. Windows and the `semihosting` feature report a
+/// full output differently, so use the sentinel `-2`, which neither returns as an error code.
+pub const EAGAIN: i32 = crate::sys::EAGAIN;
+
+/// Where output ends up: a way of writing bytes to a file descriptor.
+///
+/// The macros always write through [`LibCBackend`]. Implement this to send output somewhere
+/// else, such as a UART or a debug port, and write through it with a [`BackendWriter`]:
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::{Backend, BackendWriter};
+///
+/// /// Sends everything to a UART, whatever the file descriptor.
+/// struct Uart;
+///
+/// impl Backend for Uart {
+///     fn write(_fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+///         # let uart_send = |_: &[u8]| {};
+///         uart_send(bytes);
+///         Ok(bytes.len())
+///     }
+/// }
+///
+/// type Console = BackendWriter<Uart>;
+/// writeln!(Console::stdout(), "{} widgets", 3).unwrap();
+/// ```
+pub trait Backend {
+    /// Writes some of `bytes` to `fd`, returning the number of bytes written, or an error
+    /// code on failure.
+    ///
+    /// The caller continues short writes, retries writes that fail with [`EINTR`], and waits
+    /// and retries writes that fail with [`EAGAIN`].
+    fn write(fd: i32, bytes: &[u8]) -> Result<usize, i32>;
+
+    /// Waits a moment for `fd` to accept more bytes after a write failed with [`EAGAIN`].
+    /// Spins once by default.
+    #[inline]
+    fn wait_writable(fd: i32) {
+        core::hint::spin_loop();
+    }
+
+    /// Flushes anything buffered for `fd`. Does nothing by default.
+    #[inline]
+    fn flush(fd: i32) -> Result<(), i32> {
+        Ok(())
+    }
+}
+
+/// The backend used by the macros: the C library's `write`, or the system calls, `WriteFile`
+/// or semihosting calls that replace it with the `no-libc` and `semihosting` features.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LibCBackend;
+
+impl Backend for LibCBackend {
+    #[inline]
+    fn write(fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+        // SAFETY: the platform write primitives accept any descriptor value
+        unsafe { crate::libc_write(fd, bytes) }
+    }
+
+    #[inline]
+    fn wait_writable(fd: i32) {
+        crate::wait_writable(fd);
+    }
+
+    #[inline]
+    fn flush(_fd: i32) -> Result<(), i32> {
+        crate::sync_with_stdio();
+        Ok(())
+    }
+}
+
+/// A writer that implements [`core::fmt::Write`] by writing to a file descriptor through the
+/// backend `B`.
+///
+/// Like [`LibCWriter`](crate::LibCWriter), writes are not buffered and errors are returned to
+/// the caller. Unlike it, the file descriptor is handed to the backend as is, without going
+/// through [`set_stdout_fd`](crate::set_stdout_fd) or the output hook.
+pub struct BackendWriter<B: Backend> {
+    fd: i32,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend> BackendWriter<B> {
+    /// Creates a writer for the given file descriptor.
+    #[inline]
+    pub const fn new(fd: i32) -> BackendWriter<B> {
+        BackendWriter {
+            fd,
+            backend: PhantomData,
+        }
+    }
+
+    /// Creates a writer for the standard output.
+    #[inline]
+    pub const fn stdout() -> BackendWriter<B> {
+        BackendWriter::new(crate::__LIBC_STDOUT)
+    }
+
+    /// Creates a writer for the standard error.
+    #[inline]
+    pub const fn stderr() -> BackendWriter<B> {
+        BackendWriter::new(crate::__LIBC_STDERR)
+    }
+
+    /// Flushes anything the backend has buffered.
+    #[inline]
+    pub fn flush(&mut self) -> core::fmt::Result {
+        B::flush(self.fd).map_err(|_| core::fmt::Error)
+    }
+}
+
+impl<B: Backend> core::fmt::Write for BackendWriter<B> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        match crate::write_with::<B>(self.fd, s.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                crate::panic::write_failed(self.fd);
+                Err(core::fmt::Error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    static RECORDED: Mutex<Vec<(i32, u8)>> = Mutex::new(Vec::new());
+
+    /// Accepts one byte per call, after first being interrupted.
+    struct Trickle;
+
+    impl Backend for Trickle {
+        fn write(fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+            let mut recorded = RECORDED.lock().unwrap();
+            if recorded.last() == Some(&(fd, b'!')) {
+                return Err(5);
+            }
+            if recorded.last() != Some(&(fd, 0)) {
+                recorded.push((fd, 0));
+                return Err(EINTR);
+            }
+            recorded.push((fd, bytes[0]));
+            Ok(1)
+        }
+    }
+
+    static WAITS: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+
+    /// Is full until it has been waited on.
+    struct Full;
+
+    impl Backend for Full {
+        fn write(fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+            if !WAITS.lock().unwrap().contains(&fd) {
+                return Err(EAGAIN);
+            }
+            Ok(bytes.len())
+        }
+
+        fn wait_writable(fd: i32) {
+            WAITS.lock().unwrap().push(fd);
+        }
+    }
+
+    #[test]
+    fn test_backend_would_block() {
+        // The file descriptor isn't real, so it must never reach poll
+        write!(BackendWriter::<Full>::new(-9), "waited").unwrap();
+        assert_eq!(*WAITS.lock().unwrap(), [-9]);
+    }
+
+    #[test]
+    fn test_backend_writer() {
+        let mut w = BackendWriter::<Trickle>::new(7);
+        write!(w, "{}!", 12).unwrap();
+        w.flush().unwrap();
+        let recorded = RECORDED.lock().unwrap().clone();
+        let bytes: Vec<u8> = recorded
+            .iter()
+            .filter(|&&(fd, b)| fd == 7 && b != 0)
+            .map(|&(_, b)| b)
+            .collect();
+        assert_eq!(bytes, b"12!");
+
+        // Errors other than interruptions are returned
+        #[cfg(not(feature = "abort-on-error"))]
+        assert!(write!(w, "x").is_err());
+
+        writeln!(
+            BackendWriter::<LibCBackend>::stdout(),
+            "through the default backend"
+        )
+        .unwrap();
+    }
+}
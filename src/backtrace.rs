@@ -0,0 +1,143 @@
+use core::fmt::Write;
+
+/// Macro for printing the return addresses found by walking the frame-pointer chain to the
+/// standard error, one per line.
+///
+/// This works without any unwinding information or `backtrace()` support in the C library,
+/// but requires that the program is compiled with frame pointers (ie:
+/// `-C force-frame-pointers=yes`). The walk stops at a null or misaligned frame pointer, at a
+/// frame that doesn't move up the stack, or after `max_frames` frames.
+///
+/// Only available on `x86_64` and `aarch64`.
+///
+/// # Safety
+///
+/// The walk reads whatever memory the frame-pointer chain points at. If any function on the
+/// stack was compiled without frame pointers, this may read arbitrary memory, so it must be
+/// invoked in an `unsafe` block.
+///
+/// ```rust
+/// # use libc_print::libc_fp_walk;
+/// # fn test() {
+/// unsafe { libc_fp_walk!(16) };
+/// # }
+/// ```
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[macro_export]
+macro_rules! libc_fp_walk {
+    ($max_frames:expr) => {
+        $crate::__libc_fp_walk($max_frames)
+    };
+}
+
+#[doc(hidden)]
+#[inline(never)]
+pub unsafe fn __libc_fp_walk(max_frames: usize) {
+    let mut stm = crate::LibCWriter::new(crate::__LIBC_STDERR);
+    unsafe { walk(&mut stm, frame_pointer(), max_frames) };
+}
+
+/// Writes the return addresses in the frame-pointer chain starting at `fp` to `stm`.
+unsafe fn walk<W: Write>(stm: &mut W, mut fp: usize, max_frames: usize) {
+    for i in 0..max_frames {
+        if fp == 0 || fp & (core::mem::align_of::<usize>() - 1) != 0 {
+            break;
+        }
+
+        // Both x86_64 and aarch64 store the caller's frame pointer at [fp] and the return
+        // address immediately above it.
+        let frame = fp as *const usize;
+        let (next, ret) = unsafe {
+            (
+                core::ptr::read_volatile(frame),
+                core::ptr::read_volatile(frame.add(1)),
+            )
+        };
+        if ret == 0 {
+            break;
+        }
+
+        #[allow(unused_must_use)]
+        {
+            write!(stm, "  #{} {:#018x}", i, ret);
+            stm.write_str(crate::__LIBC_NEWLINE);
+        }
+
+        // The stack grows down, so every caller's frame must be above ours
+        if next <= fp {
+            break;
+        }
+        fp = next;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn frame_pointer() -> usize {
+    let fp: usize;
+    // SAFETY: reading a register has no side effects
+    unsafe {
+        core::arch::asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+    fp
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn frame_pointer() -> usize {
+    let fp: usize;
+    // SAFETY: reading a register has no side effects
+    unsafe {
+        core::arch::asm!("mov {}, x29", out(reg) fp, options(nomem, nostack, preserves_flags));
+    }
+    fp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_fp_walk_no_frames() {
+        // Without `-C force-frame-pointers` we can't rely on the chain being valid
+        unsafe { libc_fp_walk!(0) };
+    }
+
+    #[test]
+    fn test_fp_walk_fake_frames() {
+        // Each frame is [next_fp, ret], and the chain moves up the stack like a real one
+        let mut frames = [[0_usize; 2]; 4];
+        for (i, frame) in frames.iter_mut().enumerate() {
+            frame[1] = 0x1000 + i;
+        }
+        for i in 0..frames.len() - 1 {
+            frames[i][0] = &frames[i + 1] as *const _ as usize;
+        }
+        let fp = &frames[0] as *const _ as usize;
+
+        let mut out = String::new();
+        unsafe { walk(&mut out, fp, 16) };
+        let nl = crate::__LIBC_NEWLINE;
+        let expected = std::format!(
+            "  #0 0x0000000000001000{nl}  #1 0x0000000000001001{nl}  #2 0x0000000000001002{nl}  #3 0x0000000000001003{nl}",
+            nl = nl
+        );
+        assert_eq!(out, expected);
+
+        // The walk stops after `max_frames` frames
+        let mut out = String::new();
+        unsafe { walk(&mut out, fp, 2) };
+        let expected = std::format!(
+            "  #0 0x0000000000001000{nl}  #1 0x0000000000001001{nl}",
+            nl = nl
+        );
+        assert_eq!(out, expected);
+
+        // A misaligned or null frame pointer ends the walk immediately
+        let mut out = String::new();
+        unsafe { walk(&mut out, fp + 1, 16) };
+        unsafe { walk(&mut out, 0, 16) };
+        assert_eq!(out, "");
+    }
+}
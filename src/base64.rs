@@ -0,0 +1,121 @@
+use core::fmt::Write;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The number of input bytes encoded per write. Must be a multiple of 3 so that padding only
+/// ever appears at the very end of the output.
+const BASE64_CHUNK: usize = 48;
+
+/// Macro for printing a byte slice to the standard output as standard, padded base64, with a
+/// newline.
+///
+/// ```rust
+/// # use libc_print::libc_base64ln;
+/// libc_base64ln!(b"\x00\xffbinary");
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_base64ln {
+    ($bytes:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                $crate::__libc_line_prefix(&mut stm);
+                $crate::__libc_base64(&mut stm, $bytes);
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing a byte slice to the standard error as standard, padded base64, with a
+/// newline.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_ebase64ln {
+    ($bytes:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_line_prefix(&mut stm);
+                $crate::__libc_base64(&mut stm, $bytes);
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Streams `bytes` to `w` as base64, encoding a chunk at a time into a stack buffer.
+#[doc(hidden)]
+pub fn __libc_base64<W: Write>(w: &mut W, bytes: &[u8]) -> core::fmt::Result {
+    let mut out = [0_u8; BASE64_CHUNK / 3 * 4];
+
+    for chunk in bytes.chunks(BASE64_CHUNK) {
+        let mut len = 0;
+        for group in chunk.chunks(3) {
+            let b = [
+                group[0],
+                group.get(1).copied().unwrap_or(0),
+                group.get(2).copied().unwrap_or(0),
+            ];
+            let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+            for (i, c) in out[len..len + 4].iter_mut().enumerate() {
+                *c = if i <= group.len() {
+                    BASE64_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f]
+                } else {
+                    b'='
+                };
+            }
+            len += 4;
+        }
+
+        // SAFETY: the output only contains ASCII from the alphabet and padding
+        w.write_str(unsafe { core::str::from_utf8_unchecked(&out[..len]) })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    fn encode(bytes: &[u8]) -> String {
+        let mut s = String::new();
+        __libc_base64(&mut s, bytes).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_base64_padding() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foob"), "Zm9vYg==");
+        assert_eq!(encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_base64_chunks() {
+        let bytes = [b'x'; BASE64_CHUNK * 2 + 1];
+        let s = encode(&bytes);
+        assert_eq!(s.len(), bytes.len().div_ceil(3) * 4);
+        assert!(s.ends_with("eA=="));
+        assert!(s[..s.len() - 4].bytes().all(|c| c != b'='));
+    }
+
+    #[test]
+    fn test_base64ln() {
+        libc_base64ln!(b"stdout");
+        libc_ebase64ln!(&[0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,83 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The number of consecutive times a write to a non-blocking descriptor may fail with
+/// `EAGAIN` before we give up, with [`WouldBlock::Retry`]. On Unix each attempt first waits
+/// up to [`WOULD_BLOCK_WAIT_MS`](crate::WOULD_BLOCK_WAIT_MS) for the descriptor to become
+/// writable, so a descriptor that never drains stalls a write for about 100ms in total.
+const WOULD_BLOCK_RETRIES: u32 = 100;
+
+static POLICY: AtomicU8 = AtomicU8::new(WouldBlock::Retry as u8);
+
+/// What to do when a write fails because a non-blocking descriptor is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WouldBlock {
+    /// Give up immediately, dropping the rest of the output.
+    Drop,
+    /// Wait for the descriptor to drain, giving up after about 100ms. This is the default.
+    Retry,
+    /// Wait for the descriptor to drain for as long as it takes.
+    Block,
+}
+
+/// Sets what happens when output can't be written because a non-blocking descriptor is
+/// full, returning the previous setting.
+///
+/// ```rust
+/// # use libc_print::{set_would_block, WouldBlock};
+/// // Never stall, even if a log pipe's reader falls behind
+/// let previous = set_would_block(WouldBlock::Drop);
+/// assert_eq!(previous, WouldBlock::Retry);
+/// ```
+///
+/// The setting is process-wide. Writes interrupted by a signal are always retried.
+pub fn set_would_block(policy: WouldBlock) -> WouldBlock {
+    match POLICY.swap(policy as u8, Ordering::Relaxed) {
+        p if p == WouldBlock::Drop as u8 => WouldBlock::Drop,
+        p if p == WouldBlock::Block as u8 => WouldBlock::Block,
+        _ => WouldBlock::Retry,
+    }
+}
+
+/// Returns true if a write that has failed `attempts` times in a row because the descriptor
+/// is full should be tried again.
+#[inline]
+pub(crate) fn may_retry(attempts: u32) -> bool {
+    match POLICY.load(Ordering::Relaxed) {
+        p if p == WouldBlock::Drop as u8 => false,
+        p if p == WouldBlock::Block as u8 => true,
+        _ => attempts < WOULD_BLOCK_RETRIES,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_would_block_drop() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (rd, wr) = (fds[0], fds[1]);
+
+        // Fill the pipe so that the next write would block
+        let chunk = [0_u8; 4096];
+        unsafe {
+            libc::fcntl(wr, libc::F_SETFL, libc::O_NONBLOCK);
+            while libc::write(wr, chunk.as_ptr().cast(), chunk.len()) > 0 {}
+        }
+
+        let previous = set_would_block(WouldBlock::Drop);
+        assert_eq!(
+            crate::libc_write_all(wr, b"dropped"),
+            Err(crate::Errno::from_raw(libc::EAGAIN))
+        );
+        assert!(!may_retry(0));
+        set_would_block(previous);
+        assert!(may_retry(0));
+
+        unsafe {
+            libc::close(rd);
+            libc::close(wr);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use core::fmt::Write;
+
+/// The default size of the stack buffer used by the formatting macros.
+#[doc(hidden)]
+pub const __LIBC_BUFFER_SIZE: usize = 512;
+
+/// A writer that accumulates output in a fixed-size stack buffer, only writing to the handle
+/// when the buffer fills up, when explicitly flushed, or when it is dropped.
+///
+/// This collapses the many small `write_str` calls made by `core::fmt` into as few syscalls
+/// as possible, typically one per line. With the `locked` feature, the handle's lock is held
+/// for the lifetime of the writer so that everything written through it stays together,
+/// unless it was created with [`unlocked`](Self::unlocked).
+#[doc(hidden)]
+pub struct __LibCBufWriter<const N: usize = __LIBC_BUFFER_SIZE> {
+    handle: i32,
+    buf: [u8; N],
+    len: usize,
+    #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+    timeout: Option<crate::timeout::Timeout>,
+    // Declared last so that it is released after the final flush in `drop`
+    guard: Option<crate::lock::FdGuard>,
+}
+
+impl<const N: usize> Write for __LibCBufWriter<N> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.write_bytes(s.as_bytes())
+    }
+}
+
+impl __LibCBufWriter {
+    #[inline]
+    pub fn new(handle: i32) -> __LibCBufWriter {
+        __LibCBufWriter::with_capacity(handle)
+    }
+}
+
+impl<const N: usize> __LibCBufWriter<N> {
+    #[inline]
+    pub fn with_capacity(handle: i32) -> __LibCBufWriter<N> {
+        __LibCBufWriter {
+            handle,
+            buf: [0; N],
+            len: 0,
+            #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+            timeout: None,
+            guard: Some(crate::lock::lock(handle)),
+        }
+    }
+
+    /// Creates a writer that waits at most `timeout_ms` for the handle to become writable
+    /// before each write, and drops its output once a wait times out.
+    #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+    #[inline]
+    pub(crate) fn with_timeout(handle: i32, timeout_ms: i32) -> __LibCBufWriter<N> {
+        let mut w = __LibCBufWriter::with_capacity(handle);
+        w.timeout = Some(crate::timeout::Timeout::new(timeout_ms));
+        w
+    }
+
+    /// Creates a writer that only takes the handle's lock while it writes, so other output to
+    /// the handle can be printed while it is alive.
+    #[inline]
+    pub(crate) fn unlocked(handle: i32) -> __LibCBufWriter<N> {
+        __LibCBufWriter {
+            handle,
+            buf: [0; N],
+            len: 0,
+            #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+            timeout: None,
+            guard: None,
+        }
+    }
+
+    #[inline]
+    pub fn write_fmt(&mut self, args: core::fmt::Arguments) -> core::fmt::Result {
+        Write::write_fmt(self, args)
+    }
+
+    #[inline]
+    pub fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        Write::write_str(self, s)
+    }
+
+    #[inline]
+    pub fn write_nl(&mut self) -> core::fmt::Result {
+        Write::write_str(self, crate::__LIBC_NEWLINE)
+    }
+
+    /// Writes bytes that may not be valid UTF-8.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> core::fmt::Result {
+        if bytes.len() > N - self.len {
+            self.flush()?;
+            // Too large to ever fit, so skip the buffer entirely
+            if bytes.len() >= N {
+                return self.write_out(bytes);
+            }
+        }
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Writes out anything that has been buffered so far.
+    pub fn flush(&mut self) -> core::fmt::Result {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let len = core::mem::replace(&mut self.len, 0);
+        self.write_out(&self.buf[..len])
+    }
+
+    fn write_out(&self, bytes: &[u8]) -> core::fmt::Result {
+        let _guard = match self.guard {
+            Some(_) => None,
+            None => Some(crate::lock::lock(self.handle)),
+        };
+        #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+        if let Some(timeout) = &self.timeout {
+            return timeout.write(self.handle, bytes);
+        }
+        crate::write_bytes(self.handle, bytes)
+    }
+
+    /// Returns true if any output was dropped because a writer created with
+    /// [`with_timeout`](Self::with_timeout) timed out.
+    #[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+    #[inline]
+    pub(crate) fn timed_out(&self) -> bool {
+        self.timeout.as_ref().is_some_and(|t| t.timed_out())
+    }
+}
+
+impl<const N: usize> Drop for __LibCBufWriter<N> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn read_all(rd: i32) -> ([u8; 64], usize) {
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+        (buf, n as usize)
+    }
+
+    #[test]
+    fn test_buffered_single_write() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let mut stm = __LibCBufWriter::new(fds[1]);
+        let two = "two";
+        write!(stm, "{} {} {}", 1, two, 3.0).unwrap();
+        stm.write_nl().unwrap();
+        drop(stm);
+
+        // Everything should arrive in one piece
+        let (buf, n) = read_all(fds[0]);
+        let expected = std::format!("1 two 3{}", crate::__LIBC_NEWLINE);
+        assert_eq!(&buf[..n], expected.as_bytes());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[test]
+    fn test_buffered_overflow() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let mut stm = __LibCBufWriter::<8>::with_capacity(fds[1]);
+        stm.write_str("abc").unwrap();
+        stm.write_str("defghij").unwrap();
+        stm.write_str("0123456789").unwrap();
+        stm.write_str("!").unwrap();
+        stm.flush().unwrap();
+
+        let mut out = [0_u8; 64];
+        let mut len = 0;
+        while len < 21 {
+            let (buf, n) = read_all(fds[0]);
+            out[len..len + n].copy_from_slice(&buf[..n]);
+            len += n;
+        }
+        assert_eq!(&out[..len], b"abcdefghij0123456789!");
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Printing functions that take the caller's location, for libraries that wrap this crate's
+//! macros in their own, so the location points at their caller rather than at the wrapper.
+
+use core::fmt::Arguments;
+use core::panic::Location;
+
+/// Prints a line to `fd`, prefixed with the source location `loc` like
+/// [`libc_dbg`](crate::libc_dbg): `[src/main.rs:2] message`.
+///
+/// With the `color` feature enabled, the location is highlighted when `fd` is a terminal.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+pub fn fprintln_at(fd: i32, args: Arguments, loc: &Location<'_>) {
+    let style = crate::__libc_dbg_style(fd);
+    crate::libc_fprintln!(
+        fd,
+        "{}[{}:{}]{} {}",
+        style.location,
+        loc.file(),
+        loc.line(),
+        style.reset,
+        args
+    );
+}
+
+/// Prints a line to the standard output, prefixed with the source location `loc`.
+///
+/// ```rust
+/// use core::panic::Location;
+/// use libc_print::println_at;
+///
+/// println_at(format_args!("{} widgets", 3), Location::caller());
+/// //  ^-- prints: [src/main.rs:4] 3 widgets
+/// ```
+///
+/// See [`fprintln_at`] for details.
+#[inline]
+pub fn println_at(args: Arguments, loc: &Location<'_>) {
+    fprintln_at(crate::__LIBC_STDOUT, args, loc);
+}
+
+/// Prints a line to the standard error, prefixed with the source location `loc`.
+///
+/// See [`fprintln_at`] for details.
+#[inline]
+pub fn eprintln_at(args: Arguments, loc: &Location<'_>) {
+    fprintln_at(crate::__LIBC_STDERR, args, loc);
+}
+
+/// Prints a line to the standard output, prefixed with the location this was called from.
+///
+/// It's `#[track_caller]`, so called from a function that is itself `#[track_caller]`, the
+/// location is that function's caller, however many such functions are in between:
+///
+/// ```rust
+/// use libc_print::println_caller;
+///
+/// #[track_caller]
+/// fn log(msg: &str) {
+///     println_caller(format_args!("myapp: {}", msg));
+/// }
+///
+/// log("starting");
+/// //  ^-- prints: [src/main.rs:8] myapp: starting
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[track_caller]
+#[inline]
+pub fn println_caller(args: Arguments) {
+    println_at(args, Location::caller());
+}
+
+/// Prints a line to the standard error, prefixed with the location this was called from.
+///
+/// See [`println_caller`] for details.
+#[track_caller]
+#[inline]
+pub fn eprintln_caller(args: Arguments) {
+    eprintln_at(args, Location::caller());
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn wrapper(fd: i32, msg: &str) -> u32 {
+        let loc = Location::caller();
+        fprintln_at(fd, format_args!("wrapped {}", msg), loc);
+        loc.line()
+    }
+
+    #[test]
+    fn test_fprintln_at() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        // The location is the test's, not the wrapper's
+        let (line, expected) = (wrapper(fds[1], "call"), line!());
+        assert_eq!(line, expected);
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        let nl = crate::__LIBC_NEWLINE;
+        assert_eq!(
+            std::str::from_utf8(&buf[..n as usize]).unwrap(),
+            std::format!("[{}:{}] wrapped call{}", file!(), line, nl)
+        );
+    }
+}
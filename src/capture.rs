@@ -0,0 +1,229 @@
+//! Captures what's printed to the standard output and error in a static buffer, so tests can
+//! assert on it.
+//!
+//! ```rust
+//! use libc_print::{capture, libc_eprintln, libc_println};
+//!
+//! capture::begin();
+//! libc_println!("{} widgets", 3);
+//! libc_eprintln!("out of stock");
+//! let captured = capture::take();
+//! let lines: Vec<_> = captured.as_str().unwrap().lines().collect();
+//! assert_eq!(lines, ["3 widgets", "out of stock"]);
+//! ```
+//!
+//! Capturing is process-wide, so with the default test harness anything printed by tests
+//! running at the same time is captured too. Run tests that capture one at a time, or only
+//! check that the captured output contains what you expect.
+//!
+//! Captured output isn't passed to the output hook set with
+//! [`set_output_hook`](crate::set_output_hook) or sent to the descriptors set with
+//! [`set_stdout_fd`](crate::set_stdout_fd) and [`set_stderr_fd`](crate::set_stderr_fd). Raw
+//! writes, such as those made by the `_sigsafe` macros, are never captured.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of bytes that can be captured between [`begin`] and [`take`]. Anything printed
+/// after the buffer fills up is dropped.
+pub const CAPACITY: usize = 8192;
+
+struct CaptureCell {
+    locked: AtomicBool,
+    buffer: UnsafeCell<Captured>,
+}
+
+// SAFETY: `buffer` is only accessed while `locked` is held
+unsafe impl Sync for CaptureCell {}
+
+impl CaptureCell {
+    fn with<R>(&self, f: impl FnOnce(&mut Captured) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // SAFETY: we hold the lock, so have exclusive access
+        let res = f(unsafe { &mut *self.buffer.get() });
+        self.locked.store(false, Ordering::Release);
+        res
+    }
+}
+
+static CAPTURE: CaptureCell = CaptureCell {
+    locked: AtomicBool::new(false),
+    buffer: UnsafeCell::new(Captured::new()),
+};
+
+/// Lets writes skip the lock when nothing is being captured.
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// Output captured between [`begin`] and [`take`].
+#[derive(Clone)]
+pub struct Captured {
+    buffer: [u8; CAPACITY],
+    len: usize,
+    truncated: bool,
+}
+
+impl Captured {
+    const fn new() -> Captured {
+        Captured {
+            buffer: [0; CAPACITY],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns the captured bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// Returns the captured bytes as a string, or `None` if they aren't valid UTF-8.
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(self.as_bytes()).ok()
+    }
+
+    /// Returns true if anything was dropped because the buffer was full.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(CAPACITY - self.len);
+        self.buffer[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+        self.truncated |= n < bytes.len();
+    }
+}
+
+impl core::ops::Deref for Captured {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl core::fmt::Debug for Captured {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Captured")
+            .field("bytes", &self.as_str().unwrap_or("<invalid UTF-8>"))
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+/// Discards anything captured so far, and starts capturing the standard output and error.
+pub fn begin() {
+    CAPTURE.with(|captured| {
+        captured.len = 0;
+        captured.truncated = false;
+    });
+    CAPTURING.store(true, Ordering::Release);
+}
+
+/// Stops capturing, and returns everything printed to the standard output and error since
+/// [`begin`], in the order it was written.
+pub fn take() -> Captured {
+    CAPTURING.store(false, Ordering::Release);
+    CAPTURE.with(|captured| {
+        let taken = captured.clone();
+        captured.len = 0;
+        captured.truncated = false;
+        taken
+    })
+}
+
+/// Appends `bytes` to the capture buffer if capturing and `fd` is the standard output or
+/// error, returning false if the caller should write them itself.
+#[inline]
+pub(crate) fn write(fd: i32, bytes: &[u8]) -> bool {
+    if !CAPTURING.load(Ordering::Acquire)
+        || (fd != crate::__LIBC_STDOUT && fd != crate::__LIBC_STDERR)
+    {
+        return false;
+    }
+    CAPTURE.with(|captured| captured.push(bytes));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut captured = Captured::new();
+        captured.push(b"abc");
+        assert_eq!((&*captured, captured.is_truncated()), (&b"abc"[..], false));
+        captured.push(&[b'x'; CAPACITY]);
+        assert_eq!(captured.len(), CAPACITY);
+        assert!(captured.is_truncated() && captured.as_str().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture() {
+        // Capturing would swallow the output of tests running alongside
+        let output = crate::tests::run_in_child("capture::tests::test_capture", || {
+            begin();
+            crate::libc_println!("captured {}", 42);
+            crate::libc_eprint!("and stderr");
+            // Other descriptors are left alone
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+            crate::libc_fprint!(fds[1], "not captured");
+            let captured = take();
+
+            let mut buf = [0_u8; 16];
+            let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+            assert_eq!(&buf[..n as usize], b"not captured");
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            let nl = crate::__LIBC_NEWLINE;
+            assert_eq!(
+                captured.as_str().unwrap(),
+                std::format!("captured 42{}and stderr", nl)
+            );
+            assert!(!captured.is_truncated());
+
+            crate::libc_println!("no longer captured");
+            assert!(take().is_empty());
+        });
+        crate::tests::assert_success(&output);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_with_hook() {
+        use std::sync::atomic::AtomicUsize;
+
+        let output = crate::tests::run_in_child("capture::tests::test_capture_with_hook", || {
+            static HOOKED: AtomicUsize = AtomicUsize::new(0);
+            fn hook(bytes: &[u8], _stream: crate::Stream) {
+                HOOKED.fetch_add(bytes.len(), Ordering::Relaxed);
+            }
+
+            crate::set_output_hook(Some(hook));
+            begin();
+            crate::libc_println!("captured, not hooked");
+            let captured = take();
+            crate::libc_print!("hooked");
+            crate::set_output_hook(None);
+
+            assert!(captured.starts_with(b"captured, not hooked"));
+            assert_eq!(HOOKED.load(Ordering::Relaxed), "hooked".len());
+        });
+        crate::tests::assert_success(&output);
+    }
+}
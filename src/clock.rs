@@ -0,0 +1,19 @@
+/// Returns the monotonic time since an arbitrary fixed point, as seconds and nanoseconds.
+#[cfg(not(any(windows, feature = "semihosting")))]
+pub(crate) fn monotonic_now() -> (u64, u32) {
+    let mut ts = crate::sys::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, exclusively borrowed timespec for the duration of the call
+    unsafe {
+        crate::sys::clock_gettime(crate::sys::CLOCK_MONOTONIC, &mut ts);
+    }
+    (ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+#[cfg(windows)]
+pub(crate) use crate::windows::monotonic_now;
+
+#[cfg(feature = "semihosting")]
+pub(crate) use crate::semihosting::monotonic_now;
@@ -0,0 +1,179 @@
+/// A foreground color for [`libc_println_styled`](crate::libc_println_styled) and
+/// [`libc_eprintln_styled`](crate::libc_eprintln_styled).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    /// Returns the ANSI escape sequence that switches to this color.
+    pub const fn ansi(self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Blue => "\x1b[34m",
+            Color::Magenta => "\x1b[35m",
+            Color::Cyan => "\x1b[36m",
+            Color::White => "\x1b[37m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// Macro for printing to the standard output in a color, with a newline.
+///
+/// The color is left out if the standard output isn't a terminal, or if the `NO_COLOR`
+/// environment variable is set.
+///
+/// ```rust
+/// # use libc_print::{libc_println_styled, Color};
+/// libc_println_styled!(Color::Green, "{} tests passed", 3);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_println_styled {
+    ($color:expr, $($arg:tt)*) => {
+        $crate::__libc_fprintln_styled!($crate::__LIBC_STDOUT, $color, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard error in a color, with a newline.
+///
+/// The color is left out if the standard error isn't a terminal, or if the `NO_COLOR`
+/// environment variable is set.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_eprintln_styled {
+    ($color:expr, $($arg:tt)*) => {
+        $crate::__libc_fprintln_styled!($crate::__LIBC_STDERR, $color, $($arg)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_fprintln_styled {
+    ($fd:expr, $color:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let (start, reset) = $crate::__libc_style($fd, $color);
+                let mut stm = $crate::__LibCBufWriter::new($fd);
+                $crate::__libc_line_prefix(&mut stm);
+                stm.write_str(start);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.write_str(reset);
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Returns the escape sequences that start and end `color` in output written to `fd`, or
+/// empty strings if the output shouldn't be colored.
+#[doc(hidden)]
+#[inline]
+pub fn __libc_style(fd: i32, color: Color) -> (&'static str, &'static str) {
+    if use_color(fd) {
+        (color.ansi(), RESET)
+    } else {
+        ("", "")
+    }
+}
+
+/// Returns true if output to `fd` should be colored: it must be a terminal, and `NO_COLOR`
+/// must not be set.
+fn use_color(fd: i32) -> bool {
+    crate::tty::is_terminal(fd) && !no_color()
+}
+
+/// Returns true if the `NO_COLOR` environment variable is set to a non-empty value.
+fn no_color() -> bool {
+    let name = core::ffi::CStr::from_bytes_with_nul(b"NO_COLOR\0").unwrap();
+    crate::env::with_var(name, |value| !value.is_empty()).unwrap_or(false)
+}
+
+/// The escape sequences used to highlight `libc_dbg!` output.
+#[doc(hidden)]
+pub struct __LibCDbgStyle {
+    pub location: &'static str,
+    pub value: &'static str,
+    pub reset: &'static str,
+}
+
+const PLAIN: __LibCDbgStyle = __LibCDbgStyle {
+    location: "",
+    value: "",
+    reset: "",
+};
+
+#[cfg(feature = "color")]
+const COLOR: __LibCDbgStyle = __LibCDbgStyle {
+    location: "\x1b[36m",
+    value: "\x1b[1m",
+    reset: "\x1b[0m",
+};
+
+/// Returns the style for `libc_dbg!` output written to `fd`.
+///
+/// Output is only colored if the `color` feature is enabled, `fd` is a terminal and
+/// `NO_COLOR` isn't set, so redirected output is unchanged.
+#[doc(hidden)]
+#[inline]
+pub fn __libc_dbg_style(fd: i32) -> __LibCDbgStyle {
+    #[cfg(feature = "color")]
+    if use_color(fd) {
+        return COLOR;
+    }
+    PLAIN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbg_style_redirected() {
+        // A pipe is never a terminal, so must always be left plain
+        #[cfg(unix)]
+        {
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+            let style = __libc_dbg_style(fds[1]);
+            assert_eq!((style.location, style.value, style.reset), ("", "", ""));
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+        }
+        let _ = __libc_dbg_style(crate::__LIBC_STDERR);
+    }
+
+    #[test]
+    fn test_style_redirected() {
+        #[cfg(unix)]
+        {
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+            assert_eq!(__libc_style(fds[1], Color::Red), ("", ""));
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+        }
+        assert_eq!(Color::Red.ansi(), "\x1b[31m");
+        libc_println_styled!(Color::Green, "styled {}", "stdout");
+        libc_eprintln_styled!(Color::Yellow, "styled {}", "stderr");
+    }
+}
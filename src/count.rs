@@ -0,0 +1,83 @@
+use crate::LibCWriter;
+use core::fmt::Write;
+
+/// A writer that forwards to a [`LibCWriter`] while counting the bytes it writes.
+///
+/// Useful for checking how much a piece of logging emits, or for tallying output volume.
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::{CountingWriter, LibCWriter};
+///
+/// let mut w = CountingWriter::new(LibCWriter::stdout());
+/// write!(w, "{} widgets", 3).unwrap();
+/// assert_eq!(w.bytes_written(), 9);
+/// ```
+///
+/// Only writes that succeed are counted. As writes are retried until all of the bytes are
+/// accepted, this is the total length of everything successfully written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CountingWriter {
+    inner: LibCWriter,
+    count: usize,
+}
+
+impl CountingWriter {
+    /// Creates a counting writer that writes through `inner`, starting from zero.
+    #[inline]
+    pub const fn new(inner: LibCWriter) -> CountingWriter {
+        CountingWriter { inner, count: 0 }
+    }
+
+    /// Returns the number of bytes written so far.
+    #[inline]
+    pub const fn bytes_written(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the underlying writer, discarding the count.
+    #[inline]
+    pub const fn into_inner(self) -> LibCWriter {
+        self.inner
+    }
+}
+
+impl Write for CountingWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.inner.write_str(s)?;
+        self.count += s.len();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix, not(feature = "abort-on-error")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counting_writer() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let mut w = CountingWriter::new(LibCWriter::new(fds[1]));
+        write!(w, "{}-{}", 12, 34).unwrap();
+        writeln!(w).unwrap();
+        // `writeln!` adds a plain `\n`, whatever the `crlf` feature says
+        assert_eq!(w.bytes_written(), 6);
+
+        let mut buf = [0_u8; 16];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(n as usize, w.bytes_written());
+
+        // Failed writes aren't counted
+        let mut bad = CountingWriter::new(LibCWriter::new(-1));
+        assert!(write!(bad, "lost").is_err());
+        assert_eq!(bad.bytes_written(), 0);
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Windows debugger output support.
+//!
+//! GUI processes, and DLLs loaded into them, usually have no console, so their standard output
+//! and error go nowhere. With the `debug-output` feature, everything written to the standard
+//! output and error is also sent to `OutputDebugStringW`, where it shows up in the attached
+//! debugger's output window or in DebugView.
+
+/// The number of UTF-16 code units transcoded per `OutputDebugStringW` call.
+const DEBUG_CHUNK: usize = 512;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn OutputDebugStringW(output: *const u16);
+}
+
+/// Sends a copy of `bytes` to the debugger if `fd` is the standard output or error.
+///
+/// The text is transcoded to UTF-16 on the stack, and long writes are sent in several pieces.
+/// Invalid UTF-8 and NULs, which would end the string early, are sent as U+FFFD.
+pub(crate) fn write_debug(fd: i32, bytes: &[u8]) {
+    if fd != crate::__LIBC_STDOUT && fd != crate::__LIBC_STDERR {
+        return;
+    }
+
+    // Room for a surrogate pair past the end of the chunk, and the NUL
+    let mut buf = [0_u16; DEBUG_CHUNK + 2];
+    let mut len = 0;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let (c, n) = crate::windows::decode_char(&bytes[pos..]);
+        let c = if c == '\0' {
+            char::REPLACEMENT_CHARACTER
+        } else {
+            c
+        };
+        len += c.encode_utf16(&mut buf[len..]).len();
+        pos += n;
+        if len >= DEBUG_CHUNK {
+            output(&mut buf, len);
+            len = 0;
+        }
+    }
+    if len > 0 {
+        output(&mut buf, len);
+    }
+}
+
+fn output(buf: &mut [u16; DEBUG_CHUNK + 2], len: usize) {
+    buf[len] = 0;
+    // SAFETY: `buf` is NUL-terminated
+    unsafe { OutputDebugStringW(buf.as_ptr()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_debug() {
+        // Surrogate pairs straddling the end of each chunk must still fit in the buffer
+        let mut long = std::string::String::from("x");
+        for _ in 0..DEBUG_CHUNK {
+            long.push('\u{1f600}');
+        }
+        write_debug(crate::__LIBC_STDERR, long.as_bytes());
+        write_debug(crate::__LIBC_STDOUT, b"nul \0 and invalid \xff\n");
+        write_debug(5, b"ignored");
+    }
+}
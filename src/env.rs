@@ -0,0 +1,31 @@
+use core::ffi::CStr;
+
+/// Passes the value of the environment variable `name` to `f`, or returns `None` if it isn't
+/// set.
+#[cfg(not(any(
+    target_os = "wasi",
+    target_os = "hermit",
+    feature = "semihosting",
+    all(feature = "no-libc", any(target_os = "linux", windows))
+)))]
+pub(crate) fn with_var<R>(name: &CStr, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+    // SAFETY: the name is NUL-terminated, and the value is only read before returning
+    unsafe {
+        let value = libc::getenv(name.as_ptr().cast());
+        if value.is_null() {
+            return None;
+        }
+        Some(f(CStr::from_ptr(value.cast()).to_bytes()))
+    }
+}
+
+/// The environment can't be read without the C library.
+#[cfg(any(
+    target_os = "wasi",
+    target_os = "hermit",
+    feature = "semihosting",
+    all(feature = "no-libc", any(target_os = "linux", windows))
+))]
+pub(crate) fn with_var<R>(_name: &CStr, _f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+    None
+}
@@ -0,0 +1,58 @@
+use core::fmt::{Display, Formatter};
+
+/// The OS error code from a failed write: `errno`, or the Windows error code with the
+/// `no-libc` feature.
+///
+/// ```rust
+/// # use libc_print::{libc_write_all, Errno};
+/// if let Err(err) = libc_write_all(1, b"hello\n") {
+///     panic!("write failed with error {}: {}", err.raw(), err);
+/// }
+/// ```
+///
+/// An error code of zero means the OS stopped accepting bytes without reporting an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Errno(i32);
+
+impl Errno {
+    /// Wraps an OS error code.
+    #[inline]
+    pub const fn from_raw(raw: i32) -> Errno {
+        Errno(raw)
+    }
+
+    /// Returns the OS error code.
+    #[inline]
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+}
+
+impl Display for Errno {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            0 => f.write_str("wrote zero bytes"),
+            err => write!(f, "os error {}", err),
+        }
+    }
+}
+
+impl From<Errno> for i32 {
+    #[inline]
+    fn from(err: Errno) -> i32 {
+        err.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_errno_display() {
+        assert_eq!(Errno::from_raw(9).to_string(), "os error 9");
+        assert_eq!(Errno::from_raw(0).to_string(), "wrote zero bytes");
+        assert_eq!(Errno::from_raw(9).raw(), 9);
+    }
+}
@@ -0,0 +1,91 @@
+use core::fmt::Write;
+
+/// Macro for printing an error and its chain of [`source`](core::error::Error::source)s to
+/// the standard error.
+///
+/// The error itself is printed using its `Display` implementation, followed by one
+/// `  caused by: ...` line per source.
+///
+/// ```rust
+/// # use libc_print::libc_print_error_chain;
+/// # fn test() {
+/// let err = "x".parse::<i32>().unwrap_err();
+/// libc_print_error_chain!(err);
+/// # }
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_print_error_chain {
+    ($err:expr $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_print_error_chain(&mut stm, &$err);
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn __libc_print_error_chain<W: Write>(
+    w: &mut W,
+    err: &dyn core::error::Error,
+) -> core::fmt::Result {
+    write!(w, "{}", err)?;
+    w.write_str(crate::__LIBC_NEWLINE)?;
+
+    let mut source = err.source();
+    while let Some(err) = source {
+        write!(w, "  caused by: {}", err)?;
+        w.write_str(crate::__LIBC_NEWLINE)?;
+        source = err.source();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt;
+    use std::string::String;
+
+    #[derive(Debug)]
+    struct Chained(&'static str, Option<&'static Chained>);
+
+    impl fmt::Display for Chained {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl core::error::Error for Chained {
+        fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+            self.1.map(|e| e as _)
+        }
+    }
+
+    #[test]
+    fn test_error_chain() {
+        static ROOT: Chained = Chained("disk full", None);
+        static MIDDLE: Chained = Chained("write failed", Some(&ROOT));
+        let top = Chained("could not save", Some(&MIDDLE));
+
+        let mut s = String::new();
+        __libc_print_error_chain(&mut s, &top).unwrap();
+        let nl = crate::__LIBC_NEWLINE;
+        assert_eq!(
+            s,
+            std::format!(
+                "could not save{}  caused by: write failed{}  caused by: disk full{}",
+                nl,
+                nl,
+                nl
+            )
+        );
+
+        libc_print_error_chain!(top);
+    }
+}
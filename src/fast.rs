@@ -0,0 +1,240 @@
+//! Printing of integers and strings without the `core::fmt` machinery, for hot paths and
+//! binaries where its code size matters.
+
+use core::fmt::Write;
+
+/// Room for the 20 digits of `u64::MAX`, or a sign and the 19 digits of `i64::MIN`.
+pub(crate) const INT_BUFFER: usize = 20;
+
+/// Room for `0x` and the 16 digits of `u64::MAX`.
+const HEX_BUFFER: usize = 18;
+
+/// Macro for printing values to the standard output, with a newline, without `core::fmt`.
+///
+/// Takes a comma-separated list of values implementing [`FastDisplay`] (strings, integers,
+/// `bool`, `char` and [`Hex`]), which are printed one after another with no separator.
+///
+/// ```rust
+/// # use libc_print::{libc_fastln, Hex};
+/// let n = 42;
+/// libc_fastln!("n = ", n, ", addr = ", Hex(0xdead_beef_u32));
+/// //  ^-- prints: n = 42, addr = 0xdeadbeef
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_fastln {
+    ($($arg:expr),* $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                $crate::__libc_line_prefix(&mut stm);
+                $($crate::FastDisplay::fast_fmt(&$arg, &mut stm);)*
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing values to the standard error, with a newline, without `core::fmt`.
+///
+/// See [`libc_fastln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_efastln {
+    ($($arg:expr),* $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_line_prefix(&mut stm);
+                $($crate::FastDisplay::fast_fmt(&$arg, &mut stm);)*
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// A value that can be written by [`libc_fastln`] without going through `core::fmt`.
+pub trait FastDisplay {
+    /// Writes this value to `w` using only `write_str`.
+    fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result;
+}
+
+/// Prints an integer as lowercase hexadecimal with a `0x` prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hex<T>(pub T);
+
+/// Prints an unsigned integer to `fd` in decimal, without a newline.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+pub fn libc_print_u64(fd: i32, n: u64) {
+    let mut buf = [0; INT_BUFFER];
+    let _ = crate::__libc_println(fd, format_u64(&mut buf, n));
+}
+
+/// Prints a signed integer to `fd` in decimal, without a newline.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+pub fn libc_print_i64(fd: i32, n: i64) {
+    let mut buf = [0; INT_BUFFER];
+    let _ = crate::__libc_println(fd, format_i64(&mut buf, n));
+}
+
+/// Prints an integer to `fd` as lowercase hexadecimal with a `0x` prefix, without a newline.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+pub fn libc_print_hex(fd: i32, n: u64) {
+    let mut buf = [0; HEX_BUFFER];
+    let _ = crate::__libc_println(fd, format_hex(&mut buf, n));
+}
+
+/// Formats `n` in decimal into the end of `buf`, returning the formatted part.
+pub(crate) fn format_u64(buf: &mut [u8; INT_BUFFER], mut n: u64) -> &str {
+    let mut pos = buf.len();
+    loop {
+        pos -= 1;
+        buf[pos] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    // SAFETY: we only wrote ASCII digits
+    unsafe { core::str::from_utf8_unchecked(&buf[pos..]) }
+}
+
+/// Formats `n` in decimal into the end of `buf`, returning the formatted part.
+fn format_i64(buf: &mut [u8; INT_BUFFER], n: i64) -> &str {
+    let len = format_u64(buf, n.unsigned_abs()).len();
+    let mut pos = buf.len() - len;
+    if n < 0 {
+        pos -= 1;
+        buf[pos] = b'-';
+    }
+    // SAFETY: we only wrote ASCII digits and a sign
+    unsafe { core::str::from_utf8_unchecked(&buf[pos..]) }
+}
+
+/// Formats `n` in hexadecimal with a `0x` prefix into the end of `buf`, returning the
+/// formatted part.
+fn format_hex(buf: &mut [u8; HEX_BUFFER], mut n: u64) -> &str {
+    let mut pos = buf.len();
+    loop {
+        pos -= 1;
+        buf[pos] = b"0123456789abcdef"[(n & 0xf) as usize];
+        n >>= 4;
+        if n == 0 {
+            break;
+        }
+    }
+    pos -= 2;
+    buf[pos..pos + 2].copy_from_slice(b"0x");
+    // SAFETY: we only wrote ASCII digits and letters
+    unsafe { core::str::from_utf8_unchecked(&buf[pos..]) }
+}
+
+impl<T: FastDisplay + ?Sized> FastDisplay for &T {
+    #[inline]
+    fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        (**self).fast_fmt(w)
+    }
+}
+
+impl FastDisplay for str {
+    #[inline]
+    fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        w.write_str(self)
+    }
+}
+
+impl FastDisplay for bool {
+    #[inline]
+    fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        w.write_str(if *self { "true" } else { "false" })
+    }
+}
+
+impl FastDisplay for char {
+    #[inline]
+    fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        w.write_str(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+macro_rules! fast_display_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl FastDisplay for $t {
+                #[inline]
+                fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+                    w.write_str(format_u64(&mut [0; INT_BUFFER], *self as u64))
+                }
+            }
+
+            impl FastDisplay for Hex<$t> {
+                #[inline]
+                fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+                    w.write_str(format_hex(&mut [0; HEX_BUFFER], self.0 as u64))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! fast_display_signed {
+    ($($t:ty),*) => {
+        $(
+            impl FastDisplay for $t {
+                #[inline]
+                fn fast_fmt<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+                    w.write_str(format_i64(&mut [0; INT_BUFFER], *self as i64))
+                }
+            }
+        )*
+    };
+}
+
+fast_display_unsigned!(u8, u16, u32, u64, usize);
+fast_display_signed!(i8, i16, i32, i64, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_format_ints() {
+        let mut buf = [0; INT_BUFFER];
+        assert_eq!(format_u64(&mut buf, 0), "0");
+        assert_eq!(format_u64(&mut buf, 1234), "1234");
+        assert_eq!(format_u64(&mut buf, u64::MAX), "18446744073709551615");
+        assert_eq!(format_i64(&mut buf, -7), "-7");
+        assert_eq!(format_i64(&mut buf, i64::MIN), "-9223372036854775808");
+
+        let mut buf = [0; HEX_BUFFER];
+        assert_eq!(format_hex(&mut buf, 0), "0x0");
+        assert_eq!(format_hex(&mut buf, 0xdead_beef), "0xdeadbeef");
+        assert_eq!(format_hex(&mut buf, u64::MAX), "0xffffffffffffffff");
+    }
+
+    #[test]
+    fn test_fast_display() {
+        let mut s = String::new();
+        "n=".fast_fmt(&mut s).unwrap();
+        (-5_i8).fast_fmt(&mut s).unwrap();
+        ' '.fast_fmt(&mut s).unwrap();
+        true.fast_fmt(&mut s).unwrap();
+        ' '.fast_fmt(&mut s).unwrap();
+        Hex(255_u8).fast_fmt(&mut s).unwrap();
+        assert_eq!(s, "n=-5 true 0xff");
+
+        libc_fastln!("fast ", 1_u32, ' ', Hex(0x10_usize));
+        libc_efastln!();
+        libc_print_u64(crate::__LIBC_STDOUT, 7);
+        libc_print_i64(crate::__LIBC_STDOUT, -7);
+        libc_print_hex(crate::__LIBC_STDOUT, 7);
+    }
+}
@@ -0,0 +1,154 @@
+use core::ffi::CStr;
+
+/// The permissions that [`FileWriter::open`] creates files with: `rw-r--r--`.
+const DEFAULT_MODE: u32 = 0o644;
+
+/// A file opened for writing with the C library's `open`, and closed when dropped.
+///
+/// It implements [`core::fmt::Write`], and its descriptor can be passed to
+/// [`libc_fprintln`](crate::libc_fprintln) and the other macros that take one:
+///
+/// ```rust,no_run
+/// use core::ffi::CStr;
+/// use libc_print::{libc_fprintln, FileWriter};
+///
+/// let path = CStr::from_bytes_with_nul(b"/var/log/myapp-crash.log\0").unwrap();
+/// if let Ok(log) = FileWriter::open(path, true) {
+///     libc_fprintln!(log.fd(), "exiting with {} requests in flight", 3);
+/// }
+/// ```
+///
+/// Like [`LibCWriter`](crate::LibCWriter), writes are not buffered, so nothing is lost if the
+/// process dies before the writer is dropped.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FileWriter {
+    fd: i32,
+}
+
+impl FileWriter {
+    /// Opens `path` for writing, creating it with `rw-r--r--` permissions if it doesn't
+    /// exist. With `append`, everything written is added to the end of the file, even if
+    /// another process is writing to it too; otherwise the file is truncated.
+    ///
+    /// Returns the OS error code on failure.
+    #[inline]
+    pub fn open(path: &CStr, append: bool) -> Result<FileWriter, i32> {
+        FileWriter::open_with_mode(path, append, DEFAULT_MODE)
+    }
+
+    /// Opens `path` for writing like [`open`](Self::open), but creates it with the
+    /// permissions `mode`, before the umask is applied, if it doesn't exist.
+    pub fn open_with_mode(path: &CStr, append: bool, mode: u32) -> Result<FileWriter, i32> {
+        use crate::sys::{O_APPEND, O_CLOEXEC, O_CREAT, O_TRUNC, O_WRONLY};
+
+        let flags = O_WRONLY | O_CREAT | O_CLOEXEC | if append { O_APPEND } else { O_TRUNC };
+        loop {
+            match open(path, flags, mode) {
+                Ok(fd) => return Ok(FileWriter { fd }),
+                // Opening a FIFO can block, and so be interrupted
+                Err(crate::sys::EINTR) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns the file descriptor, for use with the macros that take one.
+    #[inline]
+    pub const fn fd(&self) -> i32 {
+        self.fd
+    }
+
+    /// Returns the file descriptor without closing it, leaving it to the caller to close.
+    #[inline]
+    pub fn into_fd(self) -> i32 {
+        let fd = self.fd;
+        core::mem::forget(self);
+        fd
+    }
+}
+
+impl core::fmt::Write for FileWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        crate::__libc_println(self.fd, s)
+    }
+}
+
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        close(self.fd);
+    }
+}
+
+/// Returns the new file descriptor, or the OS error code on failure.
+#[cfg(not(all(feature = "no-libc", target_os = "linux")))]
+fn open(path: &CStr, flags: i32, mode: u32) -> Result<i32, i32> {
+    // SAFETY: `path` is NUL-terminated, and `open` reads the mode as an unsigned int
+    let fd = unsafe { libc::open(path.as_ptr().cast(), flags, mode as libc::c_uint) };
+    if fd < 0 {
+        Err(crate::errno())
+    } else {
+        Ok(fd)
+    }
+}
+
+#[cfg(not(all(feature = "no-libc", target_os = "linux")))]
+fn close(fd: i32) {
+    // SAFETY: the descriptor is owned by the writer being dropped
+    unsafe { libc::close(fd) };
+}
+
+/// Returns the new file descriptor, or the OS error code on failure.
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+fn open(path: &CStr, flags: i32, mode: u32) -> Result<i32, i32> {
+    // SAFETY: `path` is NUL-terminated
+    let fd = unsafe { crate::syscall::open(path.as_ptr(), flags, mode) };
+    if fd < 0 {
+        Err(-fd)
+    } else {
+        Ok(fd)
+    }
+}
+
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+fn close(fd: i32) {
+    // SAFETY: the descriptor is owned by the writer being dropped
+    unsafe { crate::syscall::close(fd) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn test_file_writer() {
+        let path = std::env::temp_dir().join(std::format!("libc-print-{}.log", std::process::id()));
+        let cpath = CString::new(path.as_os_str().as_bytes()).unwrap();
+
+        let mut file = FileWriter::open(&cpath, false).unwrap();
+        write!(file, "first").unwrap();
+        drop(file);
+
+        let file = FileWriter::open(&cpath, true).unwrap();
+        crate::libc_fprintln!(file.fd(), " {}", "appended");
+        let fd = file.into_fd();
+        unsafe { libc::close(fd) };
+
+        let nl = crate::__LIBC_NEWLINE;
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, std::format!("first appended{}", nl));
+
+        // Without `append`, the file starts again
+        let mut file = FileWriter::open_with_mode(&cpath, false, 0o600).unwrap();
+        write!(file, "replaced").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "replaced");
+        std::fs::remove_file(&path).unwrap();
+
+        let missing = CStr::from_bytes_with_nul(b"/nonexistent/libc-print.log\0").unwrap();
+        assert_eq!(FileWriter::open(missing, true), Err(libc::ENOENT));
+    }
+}
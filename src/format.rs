@@ -0,0 +1,70 @@
+use core::fmt::Write;
+
+/// Macro for formatting into a caller-provided byte buffer, without allocating.
+///
+/// Evaluates to `Some(&str)` borrowing the formatted portion of the buffer, or `None` if the
+/// formatted output didn't fit.
+///
+/// ```rust
+/// # use libc_print::libc_fmt_into;
+/// let mut buf = [0_u8; 32];
+/// let s = libc_fmt_into!(&mut buf, "{} + {} = {}", 1, 2, 1 + 2);
+/// assert_eq!(s, Some("1 + 2 = 3"));
+/// ```
+#[macro_export]
+macro_rules! libc_fmt_into {
+    ($buf:expr, $($arg:tt)*) => {
+        $crate::fmt_into($buf, format_args!($($arg)*))
+    };
+}
+
+/// Formats `args` into `buf`, returning the formatted portion as a `&str`, or `None` if it
+/// didn't fit.
+///
+/// See also [`libc_fmt_into`].
+pub fn fmt_into<'a>(buf: &'a mut [u8], args: core::fmt::Arguments) -> Option<&'a str> {
+    let mut w = SliceWriter::new(buf);
+    w.write_fmt(args).ok()?;
+    Some(w.into_str())
+}
+
+/// A writer that copies formatted output into a byte slice, failing once the slice is full.
+pub(crate) struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl<'a> SliceWriter<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        SliceWriter { buf, len: 0 }
+    }
+
+    pub(crate) fn into_str(self) -> &'a str {
+        // SAFETY: we only ever copy whole `&str`s into the buffer
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_fmt_into() {
+        let mut buf = [0_u8; 8];
+        assert_eq!(libc_fmt_into!(&mut buf, "{}-{}", 12, 34), Some("12-34"));
+        assert_eq!(libc_fmt_into!(&mut buf, "{}", "exactly8"), Some("exactly8"));
+        assert_eq!(libc_fmt_into!(&mut buf, "{}", "too long!"), None);
+        assert_eq!(libc_fmt_into!(&mut [], ""), Some(""));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{__LibCBufWriter, __LIBC_BUFFER_SIZE};
+use core::fmt::Write;
+
+/// A handle that collects several writes and outputs them together when it is dropped.
+///
+/// Everything written through a group is held in a stack buffer and written in a single
+/// `write` when the group goes out of scope, so a line assembled from several `write!` calls
+/// isn't split up by output from other threads.
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::stdout_group;
+///
+/// let mut g = stdout_group();
+/// for i in 0..3 {
+///     write!(g, "{} ", i).unwrap();
+/// }
+/// writeln!(g, "done").unwrap();
+/// // `g` is dropped here, printing "0 1 2 done" in one write
+/// ```
+///
+/// Output that doesn't fit in the buffer (512 bytes unless created with
+/// [`Group::with_capacity`]) is written early, in buffer-sized pieces, which output from other
+/// threads may land between. With the `locked` feature, the descriptor's lock is only taken
+/// for each write, so the rest of the program can keep printing while a group is alive.
+pub struct Group<const N: usize = __LIBC_BUFFER_SIZE> {
+    inner: __LibCBufWriter<N>,
+}
+
+/// Starts a [`Group`] of writes to the standard output.
+#[inline]
+pub fn stdout_group() -> Group {
+    Group::new(crate::__LIBC_STDOUT)
+}
+
+/// Starts a [`Group`] of writes to the standard error.
+#[inline]
+pub fn stderr_group() -> Group {
+    Group::new(crate::__LIBC_STDERR)
+}
+
+impl Group {
+    /// Starts a group of writes to the given file descriptor.
+    #[inline]
+    pub fn new(fd: i32) -> Group {
+        Group::with_capacity(fd)
+    }
+}
+
+impl<const N: usize> Group<N> {
+    /// Starts a group of writes to the given file descriptor, buffering up to `N` bytes.
+    #[inline]
+    pub fn with_capacity(fd: i32) -> Group<N> {
+        Group {
+            inner: __LibCBufWriter::unlocked(fd),
+        }
+    }
+
+    /// Writes out everything collected so far, instead of waiting for the group to be dropped.
+    #[inline]
+    pub fn flush(&mut self) -> core::fmt::Result {
+        self.inner.flush()
+    }
+}
+
+impl<const N: usize> Write for Group<N> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.inner.write_str(s)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let mut g = Group::new(fds[1]);
+        for i in 0..3 {
+            write!(g, "{} ", i).unwrap();
+        }
+        writeln!(g, "done").unwrap();
+
+        // Nothing is written until the group is dropped
+        unsafe {
+            libc::fcntl(fds[0], libc::F_SETFL, libc::O_NONBLOCK);
+        }
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(n, -1);
+
+        drop(g);
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(&buf[..n as usize], b"0 1 2 done\n");
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+
+        write!(stdout_group(), "grouped").unwrap();
+        writeln!(stderr_group(), " output").unwrap();
+    }
+
+    #[test]
+    fn test_print_while_grouping() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        // With `locked`, this would deadlock if the group held the lock while alive
+        let mut g = Group::new(fds[1]);
+        write!(g, "grouped").unwrap();
+        crate::libc_fprintln!(fds[1], "line");
+        drop(g);
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        let expected = std::format!("line{}grouped", crate::__LIBC_NEWLINE);
+        assert_eq!(&buf[..n as usize], expected.as_bytes());
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Hermit support.
+//!
+//! Hermit applications are linked with the unikernel rather than a C library, so this calls
+//! the kernel's `sys_` functions directly, which return a negated error code on failure.
+
+use core::convert::TryFrom;
+use core::ffi::c_void;
+
+pub const EINTR: i32 = 4;
+pub const EAGAIN: i32 = 11;
+pub const EWOULDBLOCK: i32 = EAGAIN;
+
+pub const POLLOUT: i16 = 0x4;
+pub const CLOCK_MONOTONIC: u64 = 4;
+
+#[repr(C)]
+pub struct pollfd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+#[repr(C)]
+pub struct timespec {
+    pub tv_sec: i64,
+    pub tv_nsec: i32,
+}
+
+extern "C" {
+    fn sys_read(fd: i32, buf: *mut u8, len: usize) -> isize;
+    fn sys_write(fd: i32, buf: *const u8, len: usize) -> isize;
+    fn sys_poll(fds: *mut pollfd, nfds: usize, timeout: i32) -> i32;
+    fn sys_clock_gettime(clock: u64, ts: *mut timespec) -> i32;
+    fn sys_getpid() -> u32;
+    fn sys_abort() -> !;
+}
+
+/// Hermit has no terminals, only a serial console or a virtio console.
+pub unsafe fn isatty(_fd: i32) -> i32 {
+    0
+}
+
+pub unsafe fn poll(fds: *mut pollfd, nfds: usize, timeout_ms: i32) -> i32 {
+    // SAFETY: the caller guarantees that `fds` is valid for `nfds` entries
+    unsafe { sys_poll(fds, nfds, timeout_ms) }
+}
+
+pub unsafe fn clock_gettime(clock: u64, ts: *mut timespec) -> i32 {
+    // SAFETY: the caller guarantees that `ts` is valid for writes
+    unsafe { sys_clock_gettime(clock, ts) }
+}
+
+/// There are no C library buffers to flush.
+pub unsafe fn fflush(_stream: *mut c_void) -> i32 {
+    0
+}
+
+pub unsafe fn getpid() -> i32 {
+    // SAFETY: `sys_getpid` has no preconditions
+    unsafe { sys_getpid() as i32 }
+}
+
+pub unsafe fn abort() -> ! {
+    // SAFETY: `sys_abort` has no preconditions
+    unsafe { sys_abort() }
+}
+
+/// Returns the number of bytes written, or the OS error code on failure.
+pub(crate) unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Result<usize, i32> {
+    // SAFETY: `bytes` is valid for reads of `bytes.len()` bytes
+    let res = unsafe { sys_write(handle, bytes.as_ptr(), bytes.len()) };
+    usize::try_from(res).map_err(|_| -res as i32)
+}
+
+/// Returns the number of bytes read, or the OS error code on failure.
+pub(crate) unsafe fn libc_read(handle: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+    let res = unsafe { sys_read(handle, buf.as_mut_ptr(), buf.len()) };
+    usize::try_from(res).map_err(|_| -res as i32)
+}
@@ -0,0 +1,129 @@
+use core::fmt::Write;
+
+/// The number of bytes shown per row when no width is given, as in `hexdump -C`.
+#[doc(hidden)]
+pub const __LIBC_HEXDUMP_WIDTH: usize = 16;
+
+/// Macro for printing a byte slice to the standard error in the canonical `hexdump -C`
+/// layout: the offset, the bytes in hex, and the printable ASCII bytes.
+///
+/// An optional second argument sets the number of bytes per row, which defaults to 16.
+///
+/// ```rust
+/// # use libc_print::libc_hexdump;
+/// libc_hexdump!(b"hello world\n");
+/// // prints:
+/// // 00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 0a              |hello world.|
+/// // 0000000c
+/// libc_hexdump!(&[0, 1, 2, 3, 4], 4);
+/// ```
+///
+/// Unlike `hexdump`, repeated rows are printed in full rather than collapsed into `*`.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_hexdump {
+    ($bytes:expr $(,)?) => {
+        $crate::libc_hexdump!($bytes, $crate::__LIBC_HEXDUMP_WIDTH)
+    };
+    ($bytes:expr, $width:expr $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_hexdump(&mut stm, $bytes, $width);
+            }
+        }
+    };
+}
+
+/// Writes `bytes` to `w` as a hexdump, one row of `width` bytes at a time.
+#[doc(hidden)]
+pub fn __libc_hexdump<W: Write>(w: &mut W, bytes: &[u8], width: usize) -> core::fmt::Result {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    let width = width.max(1);
+    for (row, chunk) in bytes.chunks(width).enumerate() {
+        write!(w, "{:08x} ", row * width)?;
+        for i in 0..width {
+            // Hex columns are split into groups of eight
+            if i & 7 == 0 {
+                w.write_str(" ")?;
+            }
+            match chunk.get(i) {
+                Some(b) => write!(w, "{:02x} ", b)?,
+                // Pad out a partial row so the ASCII column lines up
+                None => w.write_str("   ")?,
+            }
+        }
+
+        w.write_str(" |")?;
+        for &b in chunk {
+            let c = if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            };
+            w.write_char(c)?;
+        }
+        w.write_str("|")?;
+        w.write_str(crate::__LIBC_NEWLINE)?;
+    }
+
+    write!(w, "{:08x}", bytes.len())?;
+    w.write_str(crate::__LIBC_NEWLINE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    fn hexdump(bytes: &[u8], width: usize) -> String {
+        let mut s = String::new();
+        __libc_hexdump(&mut s, bytes, width).unwrap();
+        s
+    }
+
+    /// Returns `expected` with its `\n`s replaced by the line ending the dump uses.
+    fn lines(expected: &str) -> String {
+        expected.replace('\n', crate::__LIBC_NEWLINE)
+    }
+
+    #[test]
+    fn test_hexdump_rows() {
+        assert_eq!(hexdump(b"", 16), "");
+        assert_eq!(
+            hexdump(b"0123456789abcdef\x00\xffxyz", 16),
+            lines(
+                "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+                 00000010  00 ff 78 79 7a                                    |..xyz|\n\
+                 00000015\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_hexdump_width() {
+        assert_eq!(
+            hexdump(b"hello", 4),
+            lines("00000000  68 65 6c 6c  |hell|\n00000004  6f           |o|\n00000005\n")
+        );
+        assert_eq!(
+            hexdump(&[0x41; 10], 9),
+            lines(
+                "00000000  41 41 41 41 41 41 41 41  41  |AAAAAAAAA|\n\
+                 00000009  41                           |A|\n\
+                 0000000a\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_hexdump_macro() {
+        libc_hexdump!(b"hello world\n");
+        libc_hexdump!(&[0, 1, 2, 3, 4], 4);
+    }
+}
@@ -0,0 +1,101 @@
+use core::fmt::Write;
+
+/// The number of columns used by the largest bucket's bar.
+const HIST_WIDTH: usize = 40;
+
+/// A full-width bar that we slice from rather than writing one `#` at a time.
+const HIST_BAR: &str = "########################################";
+
+/// Macro for printing a histogram of bucket counts to the standard output.
+///
+/// Each bucket is printed on its own line as `label | #### count`, with the bar
+/// length proportional to the largest count in `$counts`.
+///
+/// ```rust
+/// # use libc_print::libc_hist;
+/// libc_hist!(&[12, 40, 3], &["<1ms", "<10ms", "slow"]);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_hist {
+    ($counts:expr, $labels:expr $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                $crate::__libc_hist(&mut stm, $counts, $labels);
+            }
+        }
+    };
+}
+
+/// Writes one `label | #### count` line per bucket, with bars scaled relative to the
+/// largest count. Buckets without a matching label are printed with an empty label.
+#[doc(hidden)]
+pub fn __libc_hist<W: Write>(w: &mut W, counts: &[u64], labels: &[&str]) -> core::fmt::Result {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let label_width = labels
+        .iter()
+        .take(counts.len())
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (i, &count) in counts.iter().enumerate() {
+        let label = labels.get(i).copied().unwrap_or("");
+        write!(w, "{:<width$} | ", label, width = label_width)?;
+        let bar = hist_bar_len(count, max);
+        if bar > 0 {
+            w.write_str(&HIST_BAR[..bar])?;
+            w.write_str(" ")?;
+        }
+        write!(w, "{}", count)?;
+        w.write_str(crate::__LIBC_NEWLINE)?;
+    }
+
+    Ok(())
+}
+
+/// Scales `count` to `0..=HIST_WIDTH`, making sure that non-zero buckets remain visible.
+fn hist_bar_len(count: u64, max: u64) -> usize {
+    if max == 0 || count == 0 {
+        return 0;
+    }
+    let len = (count as u128 * HIST_WIDTH as u128 / max as u128) as usize;
+    len.clamp(1, HIST_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_hist_scaling() {
+        assert_eq!(hist_bar_len(0, 0), 0);
+        assert_eq!(hist_bar_len(0, 10), 0);
+        assert_eq!(hist_bar_len(1, 1_000_000), 1);
+        assert_eq!(hist_bar_len(5, 10), HIST_WIDTH / 2);
+        assert_eq!(hist_bar_len(u64::MAX, u64::MAX), HIST_WIDTH);
+    }
+
+    #[test]
+    fn test_hist_output() {
+        let mut s = String::new();
+        __libc_hist(&mut s, &[4, 2, 0], &["<1ms", "<10ms", "slow"]).unwrap();
+        let bar = &HIST_BAR[..HIST_WIDTH / 2];
+        let nl = crate::__LIBC_NEWLINE;
+        assert_eq!(
+            s,
+            std::format!(
+                "<1ms  | {} 4{}<10ms | {} 2{}slow  | 0{}",
+                HIST_BAR,
+                nl,
+                bar,
+                nl,
+                nl
+            )
+        );
+    }
+}
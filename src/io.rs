@@ -0,0 +1,73 @@
+//! [`std::io::Write`] support, for handing the writer to code that expects it.
+
+use std::io;
+
+/// Writes through the same path as the [`core::fmt::Write`] implementation, so the output is
+/// redirected, captured and locked in the same way, but errors are returned as
+/// [`io::Error`]s carrying the OS error code.
+///
+/// ```rust
+/// use std::io::Write;
+/// use libc_print::LibCWriter;
+///
+/// writeln!(LibCWriter::stdout(), "{} widgets", 3).unwrap();
+/// ```
+///
+/// Requires the `std` feature.
+impl io::Write for crate::LibCWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match crate::libc_write_all(self.fd(), buf) {
+            Ok(n) => Ok(n),
+            Err(err) => {
+                crate::panic::write_failed(self.fd());
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Writes aren't buffered, so this only flushes the C library's streams with the
+    /// `c-stdio` feature, when writing to the standard output or error.
+    fn flush(&mut self) -> io::Result<()> {
+        #[cfg(feature = "c-stdio")]
+        if self.fd() == crate::__LIBC_STDOUT || self.fd() == crate::__LIBC_STDERR {
+            crate::sync_with_stdio();
+        }
+        Ok(())
+    }
+}
+
+impl From<crate::Errno> for io::Error {
+    fn from(err: crate::Errno) -> io::Error {
+        // Zero means the OS stopped accepting bytes without saying why
+        match err.raw() {
+            0 => io::ErrorKind::WriteZero.into(),
+            raw => io::Error::from_raw_os_error(raw),
+        }
+    }
+}
+
+#[cfg(all(test, unix, not(feature = "abort-on-error")))]
+mod tests {
+    use std::io::Write;
+
+    #[test]
+    fn test_io_write() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut w = crate::LibCWriter::new(fds[1]);
+        writeln!(w, "{} via io", 42).unwrap();
+        w.write_all(b"bytes").unwrap();
+        w.flush().unwrap();
+
+        let mut buf = [0_u8; 32];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(&buf[..n as usize], b"42 via io\nbytes");
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+
+        let err = crate::LibCWriter::new(-1).write(b"x").unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+}
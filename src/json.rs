@@ -0,0 +1,232 @@
+use crate::FastDisplay;
+use core::fmt::{Display, Write};
+
+/// Macro for printing a structured log line to the standard error, as a single JSON object.
+///
+/// Takes a level and a message, which can be anything that implements `Display`, followed by
+/// any number of `key = value` fields whose values implement [`JsonValue`]:
+///
+/// ```rust
+/// # use libc_print::libc_json;
+/// let (port, user) = (8080, "bob \"the builder\"");
+/// libc_json!("info", "listening", port = port, user = user, tls = false);
+/// //  ^-- prints: {"level":"info","msg":"listening","port":8080,"user":"bob \"the builder\"","tls":false}
+/// libc_json!("warn", format_args!("retry {} of {}", 2, 5));
+/// ```
+///
+/// Strings are escaped as they're written, and integers are formatted directly, so nothing is
+/// allocated: the line is built in the same stack buffer as the other macros use, and written
+/// out in one piece. Line prefixes and timestamps are left off, so every line is valid JSON.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_json {
+    ($level:expr, $msg:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_json_start(&mut stm, &$level, &$msg);
+                $($crate::__libc_json_field(&mut stm, stringify!($key), &$value);)*
+                stm.write_str("}");
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// A value that can be written as a field of [`libc_json`].
+///
+/// Implemented for strings, `char`, `bool`, integers, floats, [`Option`] (with `None` written
+/// as `null`), slices and [`core::fmt::Arguments`], which is written as a string.
+pub trait JsonValue {
+    /// Writes this value to `w` as JSON.
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result;
+}
+
+/// Writes the opening of a JSON line: the level and the message.
+#[doc(hidden)]
+pub fn __libc_json_start<W: Write, L: Display + ?Sized, M: Display + ?Sized>(
+    w: &mut W,
+    level: &L,
+    msg: &M,
+) -> core::fmt::Result {
+    w.write_str("{\"level\":")?;
+    write_json_string(w, level)?;
+    w.write_str(",\"msg\":")?;
+    write_json_string(w, msg)
+}
+
+/// Writes a `,"key":value` pair.
+#[doc(hidden)]
+pub fn __libc_json_field<W: Write, V: JsonValue + ?Sized>(
+    w: &mut W,
+    key: &str,
+    value: &V,
+) -> core::fmt::Result {
+    w.write_str(",")?;
+    write_json_string(w, key)?;
+    w.write_str(":")?;
+    value.write_json(w)
+}
+
+/// Writes `value` as a quoted JSON string.
+fn write_json_string<W: Write, T: Display + ?Sized>(w: &mut W, value: &T) -> core::fmt::Result {
+    w.write_str("\"")?;
+    write!(JsonEscape(w), "{}", value)?;
+    w.write_str("\"")
+}
+
+/// Escapes everything written through it for use inside a JSON string.
+struct JsonEscape<'a, W: Write>(&'a mut W);
+
+impl<W: Write> Write for JsonEscape<'_, W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        // Copy runs of characters that don't need escaping in one go
+        let mut start = 0;
+        for (i, b) in s.bytes().enumerate() {
+            let escaped = match b {
+                b'"' => "\\\"",
+                b'\\' => "\\\\",
+                b'\n' => "\\n",
+                b'\r' => "\\r",
+                b'\t' => "\\t",
+                0..=0x1f => "",
+                _ => continue,
+            };
+            self.0.write_str(&s[start..i])?;
+            if escaped.is_empty() {
+                write!(self.0, "\\u{:04x}", b)?;
+            } else {
+                self.0.write_str(escaped)?;
+            }
+            start = i + 1;
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+impl<T: JsonValue + ?Sized> JsonValue for &T {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        (**self).write_json(w)
+    }
+}
+
+impl JsonValue for str {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        write_json_string(w, self)
+    }
+}
+
+impl JsonValue for char {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        write_json_string(w, self)
+    }
+}
+
+impl JsonValue for core::fmt::Arguments<'_> {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        write_json_string(w, self)
+    }
+}
+
+impl JsonValue for bool {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        self.fast_fmt(w)
+    }
+}
+
+impl<T: JsonValue> JsonValue for Option<T> {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        match self {
+            Some(value) => value.write_json(w),
+            None => w.write_str("null"),
+        }
+    }
+}
+
+impl<T: JsonValue> JsonValue for [T] {
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        w.write_str("[")?;
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                w.write_str(",")?;
+            }
+            value.write_json(w)?;
+        }
+        w.write_str("]")
+    }
+}
+
+impl<T: JsonValue, const N: usize> JsonValue for [T; N] {
+    #[inline]
+    fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+        self[..].write_json(w)
+    }
+}
+
+macro_rules! json_value_int {
+    ($($t:ty),*) => {
+        $(
+            impl JsonValue for $t {
+                #[inline]
+                fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+                    self.fast_fmt(w)
+                }
+            }
+        )*
+    };
+}
+
+json_value_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+macro_rules! json_value_float {
+    ($($t:ty),*) => {
+        $(
+            impl JsonValue for $t {
+                /// JSON has no infinities or NaN, so those are written as `null`.
+                #[inline]
+                fn write_json<W: Write>(&self, w: &mut W) -> core::fmt::Result {
+                    if self.is_finite() {
+                        write!(w, "{}", self)
+                    } else {
+                        w.write_str("null")
+                    }
+                }
+            }
+        )*
+    };
+}
+
+json_value_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_json_line() {
+        let mut s = String::new();
+        __libc_json_start(&mut s, "info", "a \"quoted\"\tmessage\n").unwrap();
+        __libc_json_field(&mut s, "port", &8080_u16).unwrap();
+        __libc_json_field(&mut s, "delta", &-3_i64).unwrap();
+        __libc_json_field(&mut s, "ok", &true).unwrap();
+        __libc_json_field(&mut s, "path", "C:\\tmp\u{1}").unwrap();
+        __libc_json_field(&mut s, "ratio", &[0.5_f64, f64::NAN][..]).unwrap();
+        __libc_json_field(&mut s, "user", &None::<&str>).unwrap();
+        __libc_json_field(&mut s, "msg", &format_args!("{}é", 1)).unwrap();
+        assert_eq!(
+            s,
+            r#"{"level":"info","msg":"a \"quoted\"\tmessage\n","port":8080,"delta":-3,"ok":true,"path":"C:\\tmp\u0001","ratio":[0.5,null],"user":null,"msg":"1é""#
+        );
+
+        libc_json!("debug", "stderr", n = 1, tags = ["a", "b"], trailing = 'x',);
+    }
+}
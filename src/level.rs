@@ -0,0 +1,262 @@
+use core::ffi::CStr;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The maximum level, or `UNSET` until it's been read from the environment or set.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(UNSET);
+
+const UNSET: u8 = u8::MAX;
+const OFF: u8 = 0;
+
+/// Used when `LIBC_PRINT_LEVEL` isn't set, or can't be parsed.
+const DEFAULT_LEVEL: Level = Level::Info;
+
+/// Macro for printing an error to the standard error, if errors are enabled.
+///
+/// Lines are written as `[ERROR <module path>] <message>`. See [`set_max_level`] for how to
+/// choose which levels are printed.
+///
+/// ```rust
+/// # use libc_print::libc_error;
+/// libc_error!("can't reach {}", "upstream");
+/// //  ^-- prints: [ERROR mycrate::net] can't reach upstream
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_error {
+    ($($arg:tt)+) => {
+        $crate::__libc_log!($crate::Level::Error, $($arg)+)
+    };
+}
+
+/// Macro for printing a warning to the standard error, if warnings are enabled.
+///
+/// See [`libc_error`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_warn {
+    ($($arg:tt)+) => {
+        $crate::__libc_log!($crate::Level::Warn, $($arg)+)
+    };
+}
+
+/// Macro for printing an informational message to the standard output, if they're enabled.
+///
+/// See [`libc_error`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_info {
+    ($($arg:tt)+) => {
+        $crate::__libc_log!($crate::Level::Info, $($arg)+)
+    };
+}
+
+/// Macro for printing a debugging message to the standard output, if they're enabled.
+///
+/// See [`libc_error`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_debug {
+    ($($arg:tt)+) => {
+        $crate::__libc_log!($crate::Level::Debug, $($arg)+)
+    };
+}
+
+/// Macro for printing a tracing message to the standard output, if they're enabled.
+///
+/// See [`libc_error`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_trace {
+    ($($arg:tt)+) => {
+        $crate::__libc_log!($crate::Level::Trace, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_log {
+    ($level:expr, $($arg:tt)+) => {
+        {
+            let level: $crate::Level = $level;
+            if level.__enabled() {
+                $crate::libc_fprintln!(
+                    level.__fd(),
+                    "[{} {}] {}",
+                    level,
+                    module_path!(),
+                    format_args!($($arg)+)
+                );
+            }
+        }
+    };
+}
+
+/// The severity of a message printed by [`libc_error`], [`libc_warn`], [`libc_info`],
+/// [`libc_debug`] and [`libc_trace`], from most to least severe.
+///
+/// Errors and warnings are printed to the standard error, and everything else to the
+/// standard output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    const ALL: [Level; 5] = [
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// Returns true if messages at this level should be printed.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __enabled(self) -> bool {
+        Some(self) <= max_level()
+    }
+
+    /// Returns the file descriptor messages at this level are printed to.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __fd(self) -> i32 {
+        match self {
+            Level::Error | Level::Warn => crate::__LIBC_STDERR,
+            _ => crate::__LIBC_STDOUT,
+        }
+    }
+
+    fn from_u8(level: u8) -> Option<Level> {
+        Level::ALL.get(usize::from(level).checked_sub(1)?).copied()
+    }
+}
+
+impl core::fmt::Display for Level {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Sets the most verbose level printed by the leveled macros, or turns them all off with
+/// `None`, returning the previous setting.
+///
+/// ```rust
+/// # use libc_print::{libc_debug, set_max_level, Level};
+/// set_max_level(Some(Level::Debug));
+/// libc_debug!("printed");
+/// set_max_level(Some(Level::Info));
+/// libc_debug!("not printed");
+/// ```
+///
+/// Until this is called, the level is read from the `LIBC_PRINT_LEVEL` environment variable
+/// the first time a leveled macro is used, so it can be changed without recompiling. It can
+/// be `off`, `error`, `warn`, `info`, `debug` or `trace`, in any case, and defaults to `info`.
+/// The environment can only be read when linked with the C library. The setting is
+/// process-wide.
+pub fn set_max_level(level: Option<Level>) -> Option<Level> {
+    let previous = max_level();
+    MAX_LEVEL.store(level.map_or(OFF, |level| level as u8), Ordering::Relaxed);
+    previous
+}
+
+/// Returns the most verbose level printed by the leveled macros, or `None` if they're all
+/// turned off. See [`set_max_level`] for details.
+#[inline]
+pub fn max_level() -> Option<Level> {
+    let mut level = MAX_LEVEL.load(Ordering::Relaxed);
+    if level == UNSET {
+        let from_env = env_level().map_or(OFF, |level| level as u8);
+        // If it's been set since, that takes priority
+        level = MAX_LEVEL
+            .compare_exchange(UNSET, from_env, Ordering::Relaxed, Ordering::Relaxed)
+            .map_or_else(|set| set, |_| from_env);
+    }
+    Level::from_u8(level)
+}
+
+/// Reads the maximum level from `LIBC_PRINT_LEVEL`.
+fn env_level() -> Option<Level> {
+    let name = CStr::from_bytes_with_nul(b"LIBC_PRINT_LEVEL\0").unwrap();
+    crate::env::with_var(name, parse_level).unwrap_or(Some(DEFAULT_LEVEL))
+}
+
+fn parse_level(value: &[u8]) -> Option<Level> {
+    if value.eq_ignore_ascii_case(b"off") {
+        return None;
+    }
+    let level = Level::ALL
+        .iter()
+        .find(|level| value.eq_ignore_ascii_case(level.name().as_bytes()));
+    Some(level.copied().unwrap_or(DEFAULT_LEVEL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level(b"off"), None);
+        assert_eq!(parse_level(b"warn"), Some(Level::Warn));
+        assert_eq!(parse_level(b"TRACE"), Some(Level::Trace));
+        assert_eq!(parse_level(b"loud"), Some(DEFAULT_LEVEL));
+        assert_eq!(Level::from_u8(OFF), None);
+        assert_eq!(Level::from_u8(Level::Debug as u8), Some(Level::Debug));
+        assert!(Level::Error < Level::Trace);
+        assert_eq!(std::format!("[{:<5}]", Level::Warn), "[WARN ]");
+    }
+
+    // The environment can't be read without the C library
+    #[cfg(all(unix, not(all(feature = "no-libc", target_os = "linux"))))]
+    #[test]
+    fn test_env_level() {
+        // The level is process-wide, and read from the environment
+        let output = crate::tests::run_in_child_with(
+            "level::tests::test_env_level",
+            |command| {
+                command.env("LIBC_PRINT_LEVEL", "warn");
+            },
+            || {
+                assert_eq!(max_level(), Some(Level::Warn));
+                crate::libc_info!("hidden");
+                crate::libc_warn!("shown {}", 1);
+                assert_eq!(set_max_level(None), Some(Level::Warn));
+                crate::libc_error!("hidden");
+                set_max_level(Some(Level::Trace));
+                crate::libc_trace!("trace");
+                crate::libc_debug!("debug");
+            },
+        );
+        crate::tests::assert_success(&output);
+        let stdout = std::string::String::from_utf8_lossy(&output.stdout);
+        let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+        assert!(!stdout.contains("hidden"));
+        let nl = crate::__LIBC_NEWLINE;
+        assert!(stderr.contains(&std::format!(
+            "[WARN libc_print::level::tests] shown {}{}",
+            1,
+            nl
+        )));
+    }
+}
@@ -7,6 +7,52 @@
 //! [`libc_writeln`] and [`libc_ewriteln`] are provided for cases where you may not wish
 //! to pull in the overhead of the formatter code and simply wish to print C-style strings.
 //!
+//! [`libc_fprintln`] and [`libc_fprint`] print to an arbitrary file descriptor, such as one
+//! inherited from a supervisor process or a pipe, rather than the standard output or error.
+//!
+//! [`libc_error`], [`libc_warn`], [`libc_info`], [`libc_debug`] and [`libc_trace`] print
+//! messages at or above a level chosen with [`set_max_level`] or the `LIBC_PRINT_LEVEL`
+//! environment variable.
+//!
+//! Libraries that wrap these macros in their own can report their caller's location instead of
+//! the wrapper's with the `#[track_caller]` functions [`println_caller`] and
+//! [`eprintln_caller`], or pass a location explicitly to [`println_at`] and [`eprintln_at`].
+//!
+//! [`libc_readln`] and [`read_line_into`] read a line of input into a caller-provided buffer.
+//!
+//! [`try_libc_println`] and friends report write failures instead of silently ignoring them.
+//! With the `abort-on-error` feature, any failure to write aborts the process instead.
+//!
+//! On x86_64 and aarch64 Linux, the `no-libc` feature makes system calls directly rather than
+//! calling into the C library, for freestanding binaries that don't link one. On Windows, it
+//! writes to the standard handles with `WriteFile` rather than going through the C runtime.
+//!
+//! Hermit, ESP-IDF and Redox are supported, calling the Hermit kernel directly as it has no C
+//! library.
+//!
+//! Output can be sent somewhere else entirely, such as a UART, by implementing [`Backend`] and
+//! writing through a [`BackendWriter`].
+//!
+//! On Android, the `android-log` feature sends the standard output and error to logcat. On
+//! macOS and iOS, the `os-log` feature copies them to the unified log. On Unix, the `syslog`
+//! feature adds `open_syslog`, which sends them to `syslog(3)`. On Windows, the `debug-output`
+//! feature copies them to the debugger with `OutputDebugStringW`, for GUI processes that have
+//! no console.
+//!
+//! The `std` feature implements `std::io::Write` for [`LibCWriter`], for projects that mix
+//! `std` components with a `no_std` core.
+//!
+//! The `ring-buffer` feature keeps a copy of the most recent output, which a crash handler
+//! can write out with `dump_ring`.
+//!
+//! For tests, the `capture` feature adds the `capture` module, which collects what's printed
+//! to the standard output and error so it can be checked.
+//!
+//! On bare-metal ARM, the `semihosting` feature writes through the attached debugger instead,
+//! so the same macros work on Cortex-M targets without a C library. The `critical-section`
+//! feature makes each write inside a `critical_section` critical section, so lines printed
+//! from interrupt handlers don't interleave with the main loop's.
+//!
 //! ## Usage
 //!
 //! Exactly as you'd use `println!`, `eprintln!` and `dbg!`.
@@ -46,36 +92,270 @@
 
 use core::{convert::TryFrom, file, line, stringify};
 
-/// This forces a "C" library linkage
-#[cfg(not(windows))]
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+#[cfg(all(feature = "android-log", target_os = "android"))]
+mod android;
+mod assert;
+mod backend;
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+mod backtrace;
+mod base64;
+mod blocking;
+mod buffer;
+mod caller;
+#[cfg(feature = "capture")]
+pub mod capture;
+mod clock;
+mod color;
+mod count;
+#[cfg(all(feature = "debug-output", windows))]
+mod debugout;
+mod env;
+mod errno;
+mod error;
+mod fast;
+#[cfg(unix)]
+mod file;
+mod format;
+mod group;
+#[cfg(target_os = "hermit")]
+mod hermit;
+mod hexdump;
+mod hist;
+#[cfg(feature = "std")]
+mod io;
+mod json;
+mod level;
+mod lock;
+#[cfg(feature = "log")]
+pub mod logger;
+mod logline;
+mod newline;
+mod once;
+#[cfg(all(feature = "os-log", any(target_os = "macos", target_os = "ios")))]
+mod oslog;
+mod panic;
+mod prefix;
+mod ratelimit;
+#[cfg(not(any(feature = "semihosting", all(feature = "no-libc", windows))))]
+mod read;
+mod redirect;
+#[cfg(feature = "ring-buffer")]
+mod ring;
+#[cfg(feature = "semihosting")]
+mod semihosting;
+mod sigsafe;
+mod stdio;
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+mod syscall;
+#[cfg(all(feature = "syslog", unix))]
+mod syslog;
+#[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+mod timeout;
+#[cfg(feature = "timestamp")]
+mod timestamp;
+mod tty;
+#[cfg(feature = "ufmt")]
+mod uformat;
+#[cfg(windows)]
+mod windows;
+mod wrap;
+
+#[doc(hidden)]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub use backtrace::__libc_fp_walk;
+#[cfg(all(feature = "android-log", target_os = "android"))]
+pub use android::set_android_log_tag;
+#[doc(hidden)]
+pub use assert::{__libc_assert_cmp_failed, __libc_assert_failed};
+pub use backend::{Backend, BackendWriter, LibCBackend, EAGAIN, EINTR};
+#[doc(hidden)]
+pub use base64::__libc_base64;
+pub use blocking::{set_would_block, WouldBlock};
+#[doc(hidden)]
+pub use buffer::{__LibCBufWriter, __LIBC_BUFFER_SIZE};
+pub use caller::{eprintln_at, eprintln_caller, fprintln_at, println_at, println_caller};
+#[doc(hidden)]
+pub use color::{__LibCDbgStyle, __libc_dbg_style, __libc_style};
+pub use color::Color;
+pub use count::CountingWriter;
+pub use errno::Errno;
+#[doc(hidden)]
+pub use error::__libc_print_error_chain;
+pub use fast::{libc_print_hex, libc_print_i64, libc_print_u64, FastDisplay, Hex};
+#[cfg(unix)]
+pub use file::FileWriter;
+pub use format::fmt_into;
+pub use group::{stderr_group, stdout_group, Group};
+#[doc(hidden)]
+pub use hexdump::{__libc_hexdump, __LIBC_HEXDUMP_WIDTH};
+#[doc(hidden)]
+pub use hist::__libc_hist;
+#[doc(hidden)]
+pub use json::{__libc_json_field, __libc_json_start};
+pub use json::JsonValue;
+pub use level::{max_level, set_max_level, Level};
+#[cfg(feature = "log")]
+pub use logger::LibCLogger;
+#[doc(hidden)]
+pub use newline::__LibCDbgValue;
+#[doc(hidden)]
+pub use once::__LibCOnce;
+#[doc(hidden)]
+pub use panic::{__libc_abort, __libc_panic};
+pub use panic::libc_print_panic;
+#[doc(hidden)]
+pub use prefix::__libc_line_prefix;
+#[doc(hidden)]
+#[cfg(feature = "timestamp")]
+pub use prefix::__libc_tline_prefix;
+pub use prefix::{set_line_prefix, Prefix};
+#[doc(hidden)]
+pub use ratelimit::__LibCRateLimit;
+#[cfg(not(any(feature = "semihosting", all(feature = "no-libc", windows))))]
+pub use read::{read_line_into, ReadLineError};
+pub use redirect::{set_output_hook, set_stderr_fd, set_stdout_fd, OutputHook, Stream};
+#[cfg(feature = "ring-buffer")]
+pub use ring::{dump_ring, RING_CAPACITY};
+pub use sigsafe::SignalSafeWriter;
+pub use stdio::{set_sync_with_stdio, sync_with_stdio};
+#[cfg(all(feature = "syslog", unix))]
+pub use syslog::{close_syslog, open_syslog, Facility};
+#[doc(hidden)]
+#[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+pub use timeout::__LibCTimeoutWriter;
+#[doc(hidden)]
+#[cfg(feature = "timestamp")]
+pub use timestamp::__libc_timestamp;
+#[cfg(feature = "timestamp")]
+pub use timestamp::set_timestamps;
+pub use tty::is_terminal;
+#[doc(hidden)]
+pub use wrap::__LibCWrapWriter;
+
+/// This forces a "C" library linkage, unless the default `link-libc` feature is disabled to
+/// link one some other way (for example, under another name from a build script).
+#[cfg(all(
+    feature = "link-libc",
+    not(any(
+        windows,
+        target_os = "wasi",
+        target_os = "hermit",
+        feature = "semihosting",
+        all(feature = "no-libc", target_os = "linux")
+    ))
+))]
 #[link(name = "c")]
 mod c {
     extern "C" {}
 }
 
+// The functions and constants the crate needs from the OS, taken from the C library unless
+// the `no-libc` feature replaces them with direct system calls, or the `semihosting` feature
+// with calls to the debugger.
+#[cfg(not(any(
+    feature = "semihosting",
+    target_os = "hermit",
+    all(feature = "no-libc", any(target_os = "linux", windows))
+)))]
+use libc as sys;
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+use syscall as sys;
+#[cfg(all(feature = "no-libc", windows))]
+use windows as sys;
+#[cfg(feature = "semihosting")]
+use semihosting as sys;
+#[cfg(target_os = "hermit")]
+use hermit as sys;
+
 // These constants are used by the macros but we don't want to expose
 // them to library users.
 #[doc(hidden)]
+#[cfg(not(feature = "crlf"))]
 pub const __LIBC_NEWLINE: &str = "\n";
 #[doc(hidden)]
+#[cfg(feature = "crlf")]
+pub const __LIBC_NEWLINE: &str = "\r\n";
+#[doc(hidden)]
+pub const __LIBC_STDIN: i32 = 0;
+#[doc(hidden)]
 pub const __LIBC_STDOUT: i32 = 1;
 #[doc(hidden)]
 pub const __LIBC_STDERR: i32 = 2;
+#[doc(hidden)]
+pub const __LIBC_RELEASE_SILENCE: bool = cfg!(feature = "release-silence");
 
+/// A writer for a file descriptor that implements [`core::fmt::Write`].
+///
+/// This is the same writer used by the macros, and carries no state other than the file
+/// descriptor, so can be freely created wherever it's needed (including `const` contexts).
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::LibCWriter;
+///
+/// fn report(out: &mut dyn Write) -> core::fmt::Result {
+///     writeln!(out, "{} widgets", 3)
+/// }
+///
+/// report(&mut LibCWriter::stdout()).unwrap();
+/// write!(LibCWriter::stderr(), "{}", "Hello stderr").unwrap();
+/// ```
+///
+/// Unlike the macros, writes through this writer are not buffered, and errors are returned
+/// to the caller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LibCWriter {
+    fd: i32,
+}
+
+// Kept for code that named the previously hidden writer type directly.
 #[doc(hidden)]
-pub struct __LibCWriter(i32);
+pub type __LibCWriter = LibCWriter;
+
+/// Another name for [`LibCWriter`], for code that thinks of it as a file descriptor writer.
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::FdWriter;
+///
+/// writeln!(FdWriter::stdout(), "{} widgets", 3).unwrap();
+/// assert_eq!(FdWriter::new(2), FdWriter::stderr());
+/// ```
+pub type FdWriter = LibCWriter;
 
-impl core::fmt::Write for __LibCWriter {
+impl core::fmt::Write for LibCWriter {
     #[inline]
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        __libc_println(self.0, s)
+        __libc_println(self.fd, s)
     }
 }
 
-impl __LibCWriter {
+impl LibCWriter {
+    /// Creates a writer for the given file descriptor.
+    #[inline]
+    pub const fn new(fd: i32) -> LibCWriter {
+        LibCWriter { fd }
+    }
+
+    /// Creates a writer for the standard output.
+    #[inline]
+    pub const fn stdout() -> LibCWriter {
+        LibCWriter::new(__LIBC_STDOUT)
+    }
+
+    /// Creates a writer for the standard error.
+    #[inline]
+    pub const fn stderr() -> LibCWriter {
+        LibCWriter::new(__LIBC_STDERR)
+    }
+
+    /// Returns the file descriptor this writer writes to.
     #[inline]
-    pub fn new(handle: i32) -> __LibCWriter {
-        __LibCWriter(handle)
+    pub const fn fd(&self) -> i32 {
+        self.fd
     }
 
     #[inline]
@@ -85,34 +365,197 @@ impl __LibCWriter {
 
     #[inline]
     pub fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        __libc_println(self.0, s)
+        __libc_println(self.fd, s)
     }
 
+    /// Writes a newline.
     #[inline]
     pub fn write_nl(&mut self) -> core::fmt::Result {
-        __libc_println(self.0, __LIBC_NEWLINE)
+        __libc_println(self.fd, __LIBC_NEWLINE)
     }
 }
 
 #[doc(hidden)]
 #[inline]
 pub fn __libc_println(handle: i32, msg: &str) -> core::fmt::Result {
-    let msg = msg.as_bytes();
+    __libc_write_bytes(handle, msg.as_bytes())
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn __libc_write_bytes(handle: i32, bytes: &[u8]) -> core::fmt::Result {
+    let _guard = lock::lock(handle);
+    write_bytes(handle, bytes)
+}
+
+/// Writes a string followed by a newline, holding the handle's lock across both.
+///
+/// The two are copied into a stack buffer first, so that unless `msg` is too long to fit they
+/// go out in a single write.
+#[doc(hidden)]
+#[inline]
+pub fn __libc_writeln(handle: i32, msg: &str) -> core::fmt::Result {
+    let mut stm = __LibCBufWriter::new(handle);
+    __libc_line_prefix(&mut stm)?;
+    stm.write_str(msg)?;
+    stm.write_nl()?;
+    stm.flush()
+}
+
+/// Writes a C string followed by a newline, like C's `puts`.
+#[doc(hidden)]
+#[inline]
+pub fn __libc_puts(handle: i32, s: &core::ffi::CStr) -> core::fmt::Result {
+    let mut stm = __LibCBufWriter::new(handle);
+    __libc_line_prefix(&mut stm)?;
+    stm.write_bytes(s.to_bytes())?;
+    stm.write_nl()?;
+    stm.flush()
+}
+
+/// Writes the whole of `buf` to the file descriptor `fd`, returning the number of bytes
+/// written.
+///
+/// Short writes are continued and writes interrupted by a signal are retried, so on success
+/// the count is always `buf.len()`. On failure, returns the OS error code, which is `0` if the
+/// OS stopped accepting bytes without reporting an error. Some of `buf` may have been written
+/// before an error is returned.
+///
+/// ```rust
+/// # use libc_print::libc_write_all;
+/// assert_eq!(libc_write_all(1, b"hello\n"), Ok(6));
+/// ```
+pub fn libc_write_all(fd: i32, buf: &[u8]) -> Result<usize, Errno> {
+    let _guard = lock::lock(fd);
+    write_all(fd, buf).map_err(Errno::from_raw)
+}
+
+/// How long to wait for a full non-blocking descriptor to become writable before retrying.
+const WOULD_BLOCK_WAIT_MS: i32 = 1;
+
+/// Writes all of `msg`, to the capture buffer if capturing, or the output hook if one is
+/// installed.
+fn write_all(handle: i32, msg: &[u8]) -> Result<usize, i32> {
+    write_all_with(handle, msg, write_with::<LibCBackend>)
+}
+
+/// Like [`write_all`], but makes the final write to the file descriptor with `write`.
+fn write_all_with(
+    handle: i32,
+    msg: &[u8],
+    write: impl FnOnce(i32, &[u8]) -> Result<usize, i32>,
+) -> Result<usize, i32> {
+    #[cfg(feature = "ring-buffer")]
+    ring::record(msg);
+    #[cfg(feature = "capture")]
+    if capture::write(handle, msg) {
+        return Ok(msg.len());
+    }
+    if redirect::write_hook(handle, msg) {
+        return Ok(msg.len());
+    }
+    lock::critical(|| write_fd(handle, msg, write))
+}
+
+/// Writes `msg` to wherever the file descriptor is redirected, making the final write with
+/// `write`.
+fn write_fd(
+    handle: i32,
+    msg: &[u8],
+    write: impl FnOnce(i32, &[u8]) -> Result<usize, i32>,
+) -> Result<usize, i32> {
+    let handle = redirect::resolve(handle);
+    #[cfg(all(feature = "os-log", any(target_os = "macos", target_os = "ios")))]
+    oslog::write_log(handle, msg);
+    #[cfg(all(feature = "debug-output", windows))]
+    debugout::write_debug(handle, msg);
+    #[cfg(all(feature = "syslog", unix))]
+    if let Some(res) = syslog::write_log(handle, msg) {
+        return res;
+    }
+    #[cfg(all(feature = "android-log", target_os = "android"))]
+    if let Some(res) = android::write_log(handle, msg) {
+        return res;
+    }
+    if let Some(res) = stdio::write_stream(handle, msg) {
+        return res;
+    }
+    stdio::before_write();
+    write(handle, msg)
+}
 
+/// Writes all of `msg` through the backend `B`, retrying after short writes and interruptions.
+fn write_with<B: Backend>(handle: i32, msg: &[u8]) -> Result<usize, i32> {
     let mut written = 0;
+    let mut would_block = 0;
     while written < msg.len() {
-        match unsafe { libc_write(handle, &msg[written..]) } {
-            // Ignore errors
-            None | Some(0) => break,
-            Some(res) => written += res,
+        match B::write(handle, &msg[written..]) {
+            // No progress, so report the short write
+            Ok(0) => return Err(0),
+            Ok(res) => {
+                written += res;
+                would_block = 0;
+            }
+            // Interrupted by a signal before anything was written, so try again
+            Err(sys::EINTR) => continue,
+            // A non-blocking descriptor is full, so give the reader a chance to catch up
+            Err(e) if is_would_block(e) && blocking::may_retry(would_block) => {
+                would_block += 1;
+                B::wait_writable(handle);
+            }
+            Err(e) => return Err(e),
         }
     }
 
-    Ok(())
+    Ok(written)
+}
+
+#[inline]
+fn is_would_block(err: i32) -> bool {
+    err == sys::EAGAIN || err == sys::EWOULDBLOCK
+}
+
+#[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+fn wait_writable(handle: i32) {
+    timeout::poll_writable(handle, WOULD_BLOCK_WAIT_MS);
 }
 
-#[cfg(not(windows))]
-unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Option<usize> {
+#[cfg(any(windows, target_os = "wasi", feature = "semihosting"))]
+fn wait_writable(_handle: i32) {
+    core::hint::spin_loop();
+}
+
+fn write_bytes(handle: i32, msg: &[u8]) -> core::fmt::Result {
+    match write_all(handle, msg) {
+        Ok(_) => Ok(()),
+        Err(_) => {
+            panic::write_failed(handle);
+            Err(core::fmt::Error)
+        }
+    }
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn __libc_raw_write(handle: i32, bytes: &[u8]) {
+    // SAFETY: `libc_write` only reads from `bytes` for the duration of the call
+    unsafe {
+        libc_write(handle, bytes);
+    }
+}
+
+// The platform write primitives below must stay stateless, as `__libc_raw_write` relies on
+// them being async-signal-safe.
+
+/// Returns the number of bytes written, or the OS error code on failure.
+#[cfg(not(any(
+    windows,
+    target_os = "wasi",
+    target_os = "hermit",
+    feature = "semihosting",
+    all(feature = "no-libc", target_os = "linux")
+)))]
+unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Result<usize, i32> {
     usize::try_from(unsafe {
         libc::write(
             handle,
@@ -120,11 +563,24 @@ unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Option<usize> {
             bytes.len(),
         )
     })
-    .ok()
+    .map_err(|_| errno())
 }
 
-#[cfg(windows)]
-unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Option<usize> {
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+use syscall::libc_write;
+#[cfg(all(feature = "no-libc", windows))]
+use windows::libc_write;
+#[cfg(feature = "semihosting")]
+use semihosting::libc_write;
+#[cfg(target_os = "hermit")]
+use hermit::libc_write;
+
+/// Returns the number of bytes written, or the OS error code on failure.
+#[cfg(all(windows, not(feature = "no-libc")))]
+unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Result<usize, i32> {
+    if let Some(res) = windows::write_console(handle, bytes) {
+        return res;
+    }
     usize::try_from(unsafe {
         libc::write(
             handle,
@@ -132,7 +588,80 @@ unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Option<usize> {
             libc::c_uint::try_from(bytes.len()).unwrap_or(libc::c_uint::MAX),
         )
     })
-    .ok()
+    .map_err(|_| errno())
+}
+
+/// Returns the number of bytes written, or the WASI error code on failure.
+///
+/// This calls WASI's `fd_write` directly, so works without a C library.
+#[cfg(target_os = "wasi")]
+unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Result<usize, i32> {
+    #[repr(C)]
+    struct Ciovec {
+        buf: *const u8,
+        buf_len: usize,
+    }
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_write"]
+        fn wasi_fd_write(
+            fd: i32,
+            iovs: *const Ciovec,
+            iovs_len: usize,
+            nwritten: *mut usize,
+        ) -> i32;
+    }
+
+    let iov = Ciovec {
+        buf: bytes.as_ptr(),
+        buf_len: bytes.len(),
+    };
+    let mut written = 0;
+    match unsafe { wasi_fd_write(handle, &iov, 1, &mut written) } {
+        0 => Ok(written),
+        errno => Err(errno),
+    }
+}
+
+/// Reads the calling thread's `errno`.
+fn errno() -> i32 {
+    #[cfg(windows)]
+    extern "C" {
+        fn _errno() -> *mut libc::c_int;
+    }
+    #[cfg(target_os = "espidf")]
+    extern "C" {
+        fn __errno() -> *mut libc::c_int;
+    }
+
+    // SAFETY: each of these returns a pointer to the calling thread's errno
+    unsafe {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "emscripten",
+            target_os = "fuchsia",
+            target_os = "redox",
+            target_os = "dragonfly",
+            target_os = "wasi",
+        ))]
+        return *libc::__errno_location();
+        #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+        return *libc::__errno();
+        #[cfg(target_os = "espidf")]
+        return *__errno();
+        #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+        return *libc::__error();
+        #[cfg(target_os = "haiku")]
+        return *libc::_errnop();
+        #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+        return *libc::___errno();
+        #[cfg(windows)]
+        return *_errno();
+    }
+
+    #[allow(unreachable_code)]
+    0
 }
 
 /// Macro for printing to the standard output, with a newline.
@@ -151,9 +680,7 @@ macro_rules! libc_println {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDOUT);
-                stm.write_fmt(format_args!($($arg)*));
-                stm.write_nl();
+                $crate::try_libc_println!($($arg)*);
             }
         }
     };
@@ -174,8 +701,7 @@ macro_rules! libc_print {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDOUT);
-                stm.write_fmt(format_args!($($arg)*));
+                $crate::try_libc_print!($($arg)*);
             }
         }
     };
@@ -197,9 +723,7 @@ macro_rules! libc_eprintln {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDERR);
-                stm.write_fmt(format_args!($($arg)*));
-                stm.write_nl();
+                $crate::try_libc_eprintln!($($arg)*);
             }
         }
     };
@@ -220,13 +744,93 @@ macro_rules! libc_eprint {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDERR);
-                stm.write_fmt(format_args!($($arg)*));
+                $crate::try_libc_eprint!($($arg)*);
             }
         }
     };
 }
 
+/// Macro for printing to the standard output, with a newline, returning whether the write
+/// succeeded.
+///
+/// Evaluates to a [`core::fmt::Result`]. Unlike [`libc_println`], a failed write, or one that
+/// stops making progress partway through, is reported as an `Err` rather than ignored.
+///
+/// ```rust
+/// # use libc_print::try_libc_println;
+/// if try_libc_println!("Hello {}!", "stdout").is_err() {
+///     // Fall back to some other channel
+/// }
+/// ```
+///
+/// You may wish to `use libc_print::std_name::*` to use a shorter `try_println!` macro
+/// instead of this longer name.
+#[macro_export]
+macro_rules! try_libc_println {
+    ($($arg:tt)*) => {
+        $crate::try_libc_fprintln!($crate::__LIBC_STDOUT, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard output, returning whether the write succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_print {
+    ($($arg:tt)*) => {
+        $crate::try_libc_fprint!($crate::__LIBC_STDOUT, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, returning whether the write
+/// succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_eprintln {
+    ($($arg:tt)*) => {
+        $crate::try_libc_fprintln!($crate::__LIBC_STDERR, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard error, returning whether the write succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_eprint {
+    ($($arg:tt)*) => {
+        $crate::try_libc_fprint!($crate::__LIBC_STDERR, $($arg)*)
+    };
+}
+
+/// Macro for printing a static string to the standard output, returning whether the write
+/// succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_write {
+    ($arg:expr) => {
+        {
+            let mut stm = $crate::LibCWriter::new($crate::__LIBC_STDOUT);
+            stm.write_str($arg)
+        }
+    };
+}
+
+/// Macro for printing a static string to the standard error, returning whether the write
+/// succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_ewrite {
+    ($arg:expr) => {
+        {
+            let mut stm = $crate::LibCWriter::new($crate::__LIBC_STDERR);
+            stm.write_str($arg)
+        }
+    };
+}
+
 /// Macro for printing a static string to the standard output.
 ///
 /// Does not panic on failure to write - instead silently ignores errors.
@@ -235,8 +839,7 @@ macro_rules! libc_write {
     ($arg:expr) => {
         #[allow(unused_must_use)]
         {
-            let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDOUT);
-            stm.write_str($arg);
+            $crate::try_libc_write!($arg);
         }
     };
 }
@@ -250,8 +853,7 @@ macro_rules! libc_ewrite {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDERR);
-                stm.write_str($arg);
+                $crate::try_libc_ewrite!($arg);
             }
         }
     };
@@ -265,9 +867,7 @@ macro_rules! libc_writeln {
     ($arg:expr) => {
         #[allow(unused_must_use)]
         {
-            let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDOUT);
-            stm.write_str($arg);
-            stm.write_nl();
+            $crate::__libc_writeln($crate::__LIBC_STDOUT, $arg);
         }
     };
 }
@@ -281,14 +881,217 @@ macro_rules! libc_ewriteln {
         {
             #[allow(unused_must_use)]
             {
-                let mut stm = $crate::__LibCWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_writeln($crate::__LIBC_STDERR, $arg);
+            }
+        }
+    };
+}
+
+/// Macro for printing a C string to the standard output, with a newline, like C's `puts`.
+///
+/// Takes a `&CStr`, whose bytes up to the terminating NUL are written verbatim without being
+/// checked for valid UTF-8.
+///
+/// ```rust
+/// # use libc_print::libc_puts;
+/// use core::ffi::CStr;
+///
+/// let s = CStr::from_bytes_with_nul(b"from C\0").unwrap();
+/// libc_puts!(s);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_puts {
+    ($s:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::__libc_puts($crate::__LIBC_STDOUT, $s);
+            }
+        }
+    };
+}
+
+/// Macro for printing a C string to the standard error, with a newline.
+///
+/// See [`libc_puts`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_eputs {
+    ($s:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::__libc_puts($crate::__LIBC_STDERR, $s);
+            }
+        }
+    };
+}
+
+/// Macro for writing raw bytes to the standard output, bypassing `core::fmt` entirely.
+///
+/// The bytes are written verbatim and don't need to be valid UTF-8.
+///
+/// ```rust
+/// # use libc_print::libc_write_bytes;
+/// let payload = b"\xffpayload";
+/// libc_write_bytes!(&[payload.len() as u8]);
+/// libc_write_bytes!(payload);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_write_bytes {
+    ($bytes:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::__libc_write_bytes($crate::__LIBC_STDOUT, $bytes);
+            }
+        }
+    };
+}
+
+/// Macro for writing raw bytes to the standard error, bypassing `core::fmt` entirely.
+///
+/// The bytes are written verbatim and don't need to be valid UTF-8.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_write_bytes_err {
+    ($bytes:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::__libc_write_bytes($crate::__LIBC_STDERR, $bytes);
+            }
+        }
+    };
+}
+
+/// Macro for printing to an arbitrary file descriptor, with a newline.
+///
+/// The file descriptor is evaluated at runtime, so can be one inherited from a supervisor
+/// process or created by `pipe`.
+///
+/// ```rust
+/// # use libc_print::libc_fprintln;
+/// libc_fprintln!(1, "status: {}", 200);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_fprintln {
+    ($fd:expr $(,)?) => { $crate::libc_fprintln!($fd, "") };
+    ($fd:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::try_libc_fprintln!($fd, $($arg)*);
+            }
+        }
+    };
+}
+
+/// Macro for printing to an arbitrary file descriptor.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_fprint {
+    ($fd:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::try_libc_fprint!($fd, $($arg)*);
+            }
+        }
+    };
+}
+
+/// Macro for printing to an arbitrary file descriptor, with a newline, returning whether the
+/// write succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_fprintln {
+    ($fd:expr $(,)?) => { $crate::try_libc_fprintln!($fd, "") };
+    ($fd:expr, $($arg:tt)*) => {
+        {
+            let mut stm = $crate::__LibCBufWriter::new($fd);
+            $crate::__libc_line_prefix(&mut stm)
+                .and_then(|_| stm.write_fmt(format_args!($($arg)*)))
+                .and_then(|_| stm.write_nl())
+                .and_then(|_| stm.flush())
+        }
+    };
+}
+
+/// Macro for printing to an arbitrary file descriptor, returning whether the write
+/// succeeded.
+///
+/// See [`try_libc_println`] for details.
+#[macro_export]
+macro_rules! try_libc_fprint {
+    ($fd:expr, $($arg:tt)*) => {
+        {
+            let mut stm = $crate::__LibCBufWriter::new($fd);
+            stm.write_fmt(format_args!($($arg)*)).and_then(|_| stm.flush())
+        }
+    };
+}
+
+/// Macro for printing a static string to an arbitrary file descriptor.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_fwrite {
+    ($fd:expr, $arg:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::LibCWriter::new($fd);
                 stm.write_str($arg);
-                stm.write_nl();
             }
         }
     };
 }
 
+/// Macro for printing a static string to an arbitrary file descriptor, with a newline.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_fwriteln {
+    ($fd:expr, $arg:expr) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                $crate::__libc_writeln($fd, $arg);
+            }
+        }
+    };
+}
+
+/// Macro for writing a static byte string to a file descriptor with a single, direct
+/// `write` call.
+///
+/// This is the last-resort output path for the most constrained fault contexts (ie: signal
+/// or double-fault handlers): it doesn't format, retry, buffer, lock or consult any of this
+/// crate's global state, and is async-signal-safe. Output may therefore be truncated if the
+/// OS performs a short write.
+///
+/// ```rust
+/// # use libc_print::libc_raw_panic_write;
+/// libc_raw_panic_write!(2, b"fatal: out of stack\n");
+/// ```
+#[macro_export]
+macro_rules! libc_raw_panic_write {
+    ($fd:expr, $bytes:literal) => {
+        $crate::__libc_raw_write($fd, $bytes)
+    };
+}
+
 /// Prints and returns the value of a given expression for quick and dirty
 /// debugging.
 ///
@@ -303,23 +1106,172 @@ macro_rules! libc_ewriteln {
 ///
 /// See [dbg!](https://doc.rust-lang.org/std/macro.dbg.html) for full documentation.
 ///
+/// With the `color` feature enabled, the location and value are highlighted when the
+/// standard error is a terminal. Redirected output is never colored.
+///
+/// With the `crlf` feature enabled, the lines of the pretty-printed value end in `\r\n`, like
+/// the rest of the output.
+///
+/// With the `release-silence` feature enabled, nothing is printed in builds without
+/// `debug_assertions`, but the value is still evaluated and returned.
+///
 /// You may wish to `use libc_print::std_name::*` to use a replacement
 /// `dbg!` macro instead of this longer name.
 #[macro_export]
 macro_rules! libc_dbg {
     () => {
-        $crate::libc_eprintln!("[{}:{}]", $file!(), $line!())
+        $crate::libc_dbg_to!($crate::__LIBC_STDERR)
+    };
+    ($($val:expr),+ $(,)?) => {
+        $crate::libc_dbg_to!($crate::__LIBC_STDERR, $($val),+)
+    };
+}
+
+/// Prints and returns the value of a given expression, like [`libc_dbg`], but to an arbitrary
+/// file descriptor rather than the standard error.
+///
+/// ```rust
+/// # use libc_print::libc_dbg_to;
+/// let a = 2;
+/// let b = libc_dbg_to!(1, a * 2) + 1;
+/// //      ^-- prints to the standard output: [src/main.rs:2] a * 2 = 4
+/// assert_eq!(b, 5);
+/// ```
+///
+/// With the `color` feature enabled, the output is highlighted when the file descriptor is a
+/// terminal.
+#[macro_export]
+macro_rules! libc_dbg_to {
+    ($fd:expr $(,)?) => {
+        {
+            let fd = $fd;
+            if $crate::__libc_dbg_enabled!() {
+                let style = $crate::__libc_dbg_style(fd);
+                $crate::libc_fprintln!(fd, "{}[{}:{}]{}", style.location, file!(), line!(), style.reset)
+            }
+        }
     };
-    ($val:expr $(,)?) => {
-        match $val {
-            tmp => {
-                $crate::libc_eprintln!("[{}:{}] {} = {:#?}", file!(), line!(), stringify!($val), &tmp);
+    ($fd:expr, $($val:expr),+ $(,)?) => {
+        $crate::__libc_dbg_to!("{:#?}", $fd, $($val),+)
+    };
+}
+
+/// The body of [`libc_dbg_to`] and its variants, formatting each value with `$fmt`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_dbg_to {
+    ($fmt:literal, $fd:expr, $val:expr) => {
+        match ($fd, $val) {
+            (fd, tmp) => {
+                if $crate::__libc_dbg_enabled!() {
+                    let style = $crate::__libc_dbg_style(fd);
+                    $crate::libc_fprintln!(
+                        fd,
+                        concat!("{}[{}:{}]{} {} = {}", $fmt, "{}"),
+                        style.location, file!(), line!(), style.reset,
+                        stringify!($val),
+                        style.value, $crate::__LibCDbgValue(&tmp), style.reset
+                    );
+                }
                 tmp
             }
         }
     };
+    ($fmt:literal, $fd:expr, $($val:expr),+) => {
+        match $fd {
+            fd => ($($crate::__libc_dbg_to!($fmt, fd, $val)),+,),
+        }
+    };
+}
+
+/// Prints and returns the value of a given expression, like [`libc_dbg`], but formats the
+/// value on a single line using `{:?}` rather than pretty-printing it with `{:#?}`.
+///
+/// ```rust
+/// # use libc_print::libc_dbg_compact;
+/// let v = libc_dbg_compact!([1, 2, 3]);
+/// //      ^-- prints: [src/main.rs:2] [1, 2, 3] = [1, 2, 3]
+/// assert_eq!(v.len(), 3);
+/// ```
+#[macro_export]
+macro_rules! libc_dbg_compact {
+    () => {
+        $crate::libc_dbg!()
+    };
+    ($($val:expr),+ $(,)?) => {
+        $crate::__libc_dbg_to!("{:?}", $crate::__LIBC_STDERR, $($val),+)
+    };
+}
+
+/// Prints and returns the value of a given expression, like [`libc_dbg`], but formats the
+/// value with `{}` using its `Display` implementation rather than `Debug`.
+///
+/// This suits types that deliberately don't implement `Debug`, to save code size.
+///
+/// With the `crlf` feature enabled, any newlines in the value are written as `\r\n`.
+///
+/// ```rust
+/// # use libc_print::libc_dbg_display;
+/// let name = "widget";
+/// let n = libc_dbg_display!(name).len();
+/// //      ^-- prints: [src/main.rs:2] name = widget
+/// assert_eq!(n, 6);
+/// ```
+#[macro_export]
+macro_rules! libc_dbg_display {
+    () => {
+        $crate::libc_dbg!()
+    };
     ($($val:expr),+ $(,)?) => {
-        ($($crate::libc_dbg!($val)),+,)
+        $crate::__libc_dbg_to!("{}", $crate::__LIBC_STDERR, $($val),+)
+    };
+}
+
+/// Macro for printing to the standard output, with a newline, only in builds with
+/// `debug_assertions` enabled.
+///
+/// In release builds the call compiles to nothing and its arguments aren't evaluated, like
+/// [`debug_assert!`](https://doc.rust-lang.org/core/macro.debug_assert.html), so diagnostics
+/// can be left in without being shipped.
+///
+/// ```rust
+/// # use libc_print::libc_debug_println;
+/// let state = "ready";
+/// libc_debug_println!("state = {}", state);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_debug_println {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::libc_println!($($arg)*);
+        }
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, only in builds with
+/// `debug_assertions` enabled.
+///
+/// See [`libc_debug_println`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_debug_eprintln {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::libc_eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Whether `libc_dbg!` and friends print: always, unless the `release-silence` feature is
+/// enabled and the calling crate is built without `debug_assertions`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_dbg_enabled {
+    () => {
+        (cfg!(debug_assertions) || !$crate::__LIBC_RELEASE_SILENCE)
     };
 }
 
@@ -331,6 +1283,10 @@ pub mod std_name {
     pub use super::libc_eprintln as eprintln;
     pub use super::libc_print as print;
     pub use super::libc_println as println;
+    pub use super::try_libc_eprint as try_eprint;
+    pub use super::try_libc_eprintln as try_eprintln;
+    pub use super::try_libc_print as try_print;
+    pub use super::try_libc_println as try_println;
 
     #[cfg(test)]
     mod tests_std_name {
@@ -350,6 +1306,63 @@ pub mod std_name {
 
 #[cfg(test)]
 mod tests {
+    use std::process::{Command, Output};
+
+    /// Set to the name of the test being run by [`child_command`].
+    const CHILD_ENV: &str = "LIBC_PRINT_TEST_CHILD";
+
+    /// Returns a command that runs the test `name`, such as `"ring::tests::test_dump_ring"`,
+    /// again on its own, in a child process where [`in_child`] returns true.
+    pub(crate) fn child_command(name: &str) -> Command {
+        let mut command = Command::new(std::env::current_exe().unwrap());
+        command
+            .args(["--exact", name, "--nocapture"])
+            .env(CHILD_ENV, name);
+        command
+    }
+
+    /// Returns true in the child process started by [`child_command`] for the test `name`.
+    pub(crate) fn in_child(name: &str) -> bool {
+        std::env::var_os(CHILD_ENV).is_some_and(|child| child == name)
+    }
+
+    /// Runs `f` for the test `name` in a child process, returning its output.
+    ///
+    /// For tests that change process-wide state, which would affect tests running alongside,
+    /// or that check what's printed, or that abort. The child exits once `f` returns.
+    pub(crate) fn run_in_child(name: &str, f: impl FnOnce()) -> Output {
+        run_in_child_with(name, |_| {}, f)
+    }
+
+    /// Checks that a child process started by [`run_in_child`] exited successfully, showing
+    /// its output if not.
+    pub(crate) fn assert_success(output: &Output) {
+        assert!(
+            output.status.success(),
+            "child failed with {}\n{}{}",
+            output.status,
+            std::string::String::from_utf8_lossy(&output.stdout),
+            std::string::String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Runs `f` in a child process like [`run_in_child`], after passing the command that
+    /// starts it to `configure`, for example to set environment variables.
+    pub(crate) fn run_in_child_with(
+        name: &str,
+        configure: impl FnOnce(&mut Command),
+        f: impl FnOnce(),
+    ) -> Output {
+        if in_child(name) {
+            f();
+            // Flushes the C library's streams, for the `c-stdio` feature
+            std::process::exit(0);
+        }
+        let mut command = child_command(name);
+        configure(&mut command);
+        command.output().unwrap()
+    }
+
     #[test]
     fn test_stdout() {
         super::libc_println!("stdout fd = {}", super::__LIBC_STDOUT);
@@ -370,6 +1383,295 @@ mod tests {
         super::libc_ewriteln!("stderr!");
     }
 
+    #[test]
+    fn test_public_writer() {
+        use core::fmt::Write;
+        const OUT: super::LibCWriter = super::LibCWriter::stdout();
+        let mut out = OUT;
+        writeln!(out, "stdout fd = {}", out.fd()).unwrap();
+        let err: &mut dyn Write = &mut super::LibCWriter::stderr();
+        writeln!(err, "stderr fd = {}", super::__LIBC_STDERR).unwrap();
+    }
+
+    #[test]
+    fn test_try_print() {
+        use super::std_name::*;
+        assert!(try_libc_println!("stdout fd = {}", super::__LIBC_STDOUT).is_ok());
+        assert!(try_eprintln!().is_ok());
+        assert!(try_libc_write!("").is_ok());
+    }
+
+    // The Windows CRT treats invalid descriptors as a fatal programming error
+    #[cfg(all(unix, not(feature = "abort-on-error")))]
+    #[test]
+    fn test_try_print_bad_fd() {
+        let mut stm = super::LibCWriter::new(-1);
+        assert!(write!(stm, "fd = {}", -1).is_err());
+        assert!(stm.write_nl().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fprint() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let fd = fds[1];
+
+        libc_fprint!(fd, "status: {}", 200);
+        libc_fprintln!(fd);
+        libc_fwrite!(fd, "static");
+        libc_fwriteln!(fd, "!");
+        assert!(try_libc_fprintln!(fd, "{}", fd).is_ok());
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        let nl = crate::__LIBC_NEWLINE;
+        let expected = std::format!("status: 200{}static!{}{}{}", nl, nl, fd, nl);
+        assert_eq!(&buf[..n as usize], expected.as_bytes());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_writeln_single_write() {
+        // Datagram sockets keep each write as a separate message
+        let mut fds = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+
+        libc_fwriteln!(fds[1], "one message");
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        let expected = std::format!("one message{}", crate::__LIBC_NEWLINE);
+        assert_eq!(&buf[..n as usize], expected.as_bytes());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_puts() {
+        use core::ffi::CStr;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let s = CStr::from_bytes_with_nul(b"not \xff utf-8\0").unwrap();
+        assert!(super::__libc_puts(fds[1], s).is_ok());
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        let nl = crate::__LIBC_NEWLINE.as_bytes();
+        assert_eq!(&buf[..n as usize], [&b"not \xff utf-8"[..], nl].concat());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+
+        libc_puts!(s);
+        libc_eputs!(CStr::from_bytes_with_nul(b"stderr\0").unwrap());
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        libc_write_bytes!(b"raw \xff bytes\n");
+        libc_write_bytes_err!(&[0x21, 0x0a]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_all() {
+        use super::libc_write_all;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        assert_eq!(libc_write_all(fds[1], b"framed"), Ok(6));
+        assert_eq!(libc_write_all(fds[1], b""), Ok(0));
+        let mut buf = [0_u8; 16];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(&buf[..n as usize], b"framed");
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        assert_eq!(
+            libc_write_all(fds[1], b"closed"),
+            Err(crate::Errno::from_raw(libc::EBADF))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_retry_on_eagain() {
+        use super::libc_write_all;
+        use std::{thread, vec, vec::Vec};
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [rd, wr] = fds;
+        unsafe {
+            libc::fcntl(wr, libc::F_SETFL, libc::O_NONBLOCK);
+        }
+
+        // A slow reader, so the pipe is regularly full
+        let reader = thread::spawn(move || {
+            let mut total = 0;
+            let mut buf = vec![0_u8; 4096];
+            loop {
+                thread::sleep(std::time::Duration::from_millis(1));
+                let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+                if n <= 0 {
+                    break;
+                }
+                total += n as usize;
+            }
+            total
+        });
+
+        // Larger than a pipe buffer, so the writer repeatedly sees EAGAIN
+        let bytes: Vec<u8> = vec![b'x'; 256 * 1024];
+        assert_eq!(libc_write_all(wr, &bytes), Ok(bytes.len()));
+        unsafe {
+            libc::close(wr);
+        }
+        assert_eq!(reader.join().unwrap(), bytes.len());
+        unsafe {
+            libc::close(rd);
+        }
+
+        // With nobody reading, we give up rather than spinning forever
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe {
+            libc::fcntl(fds[1], libc::F_SETFL, libc::O_NONBLOCK);
+        }
+        assert_eq!(
+            libc_write_all(fds[1], &bytes),
+            Err(crate::Errno::from_raw(libc::EAGAIN))
+        );
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_bytes_large() {
+        use std::{thread, vec, vec::Vec};
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [rd, wr] = fds;
+
+        // Larger than a pipe buffer, so this takes several writes
+        let bytes: Vec<u8> = (0..256 * 1024).map(|i| i as u8).collect();
+        let reader = thread::spawn(move || {
+            let mut out = Vec::new();
+            let mut buf = vec![0_u8; 4096];
+            loop {
+                let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+                if n <= 0 {
+                    break;
+                }
+                out.extend_from_slice(&buf[..n as usize]);
+            }
+            unsafe { libc::close(rd) };
+            out
+        });
+
+        super::__libc_write_bytes(wr, &bytes).unwrap();
+        unsafe { libc::close(wr) };
+        assert!(reader.join().unwrap() == bytes);
+    }
+
+    #[test]
+    fn test_unicode() {
+        // On a Windows console this should not appear as mojibake
+        super::libc_println!("héllo 日本語");
+    }
+
+    #[test]
+    fn test_raw_panic_write() {
+        libc_raw_panic_write!(super::__LIBC_STDOUT, b"raw stdout!\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_retry_on_eintr() {
+        use std::{thread, time::Duration, vec::Vec};
+
+        extern "C" fn on_alarm(_: libc::c_int) {}
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [rd, wr] = fds;
+
+        // Install a handler without SA_RESTART, so a blocked write fails with EINTR
+        let mut old: libc::sigaction = unsafe { core::mem::zeroed() };
+        unsafe {
+            let mut action: libc::sigaction = core::mem::zeroed();
+            action.sa_sigaction = on_alarm as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGALRM, &action, &mut old);
+        }
+
+        // Fill the pipe so that the next blocking write can't make progress
+        let mut filled = 0;
+        unsafe {
+            let flags = libc::fcntl(wr, libc::F_GETFL);
+            libc::fcntl(wr, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            let chunk = [b'.'; 512];
+            loop {
+                let res = libc::write(wr, chunk.as_ptr().cast(), chunk.len());
+                if res <= 0 {
+                    break;
+                }
+                filled += res as usize;
+            }
+            libc::fcntl(wr, libc::F_SETFL, flags);
+        }
+
+        let msg = "still here after EINTR";
+        let writer = unsafe { libc::pthread_self() } as usize;
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            unsafe { libc::pthread_kill(writer as libc::pthread_t, libc::SIGALRM) };
+            thread::sleep(Duration::from_millis(50));
+
+            // Read until the writer closes its end
+            let mut out = Vec::new();
+            let mut buf = [0_u8; 4096];
+            loop {
+                let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+                if n <= 0 {
+                    break;
+                }
+                out.extend_from_slice(&buf[..n as usize]);
+            }
+            out
+        });
+
+        super::__libc_println(wr, msg).unwrap();
+        unsafe { libc::close(wr) };
+        let out = reader.join().unwrap();
+        assert_eq!(&out[filled..], msg.as_bytes());
+
+        unsafe {
+            libc::sigaction(libc::SIGALRM, &old, core::ptr::null_mut());
+            libc::close(rd);
+        }
+    }
+
     #[test]
     fn test_dbg() {
         let a = 2;
@@ -378,6 +1680,96 @@ mod tests {
     }
 
     #[test]
+    fn test_debug_println() {
+        // The arguments are only evaluated when the line is printed
+        let mut evaluated = 0;
+        libc_debug_println!("debug {}", {
+            evaluated += 1;
+            "stdout"
+        });
+        libc_debug_eprintln!("debug {}", {
+            evaluated += 1;
+            "stderr"
+        });
+        assert_eq!(evaluated, if cfg!(debug_assertions) { 2 } else { 0 });
+
+        let b = libc_dbg!(2 + 2);
+        assert_eq!(b, 4);
+        assert_eq!(
+            __libc_dbg_enabled!(),
+            cfg!(debug_assertions) || !cfg!(feature = "release-silence")
+        );
+    }
+
+    #[test]
+    fn test_dbg_compact() {
+        #[derive(Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let p = libc_dbg_compact!(Point { x: 1, y: 2 });
+        assert_eq!(p, Point { x: 1, y: 2 });
+        let (a, b) = libc_dbg_compact!(1, "two");
+        assert_eq!((a, b), (1, "two"));
+        libc_dbg_compact!();
+    }
+
+    #[test]
+    fn test_dbg_display() {
+        /// Only implements `Display`.
+        struct Celsius(i32);
+
+        impl core::fmt::Display for Celsius {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}°C", self.0)
+            }
+        }
+
+        let t = libc_dbg_display!(Celsius(21));
+        assert_eq!(t.0, 21);
+        let (a, b) = libc_dbg_display!(1, "two",);
+        assert_eq!((a, b), (1, "two"));
+        libc_dbg_display!();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dbg_to() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let b = libc_dbg_to!(fds[1], 1 + 1) * 2;
+        assert_eq!(b, 4);
+        let (a, b) = libc_dbg_to!(fds[1], 1, "two",);
+        assert_eq!((a, b), (1, "two"));
+
+        // Nothing is written when silenced in release builds
+        if __libc_dbg_enabled!() {
+            let mut buf = [0_u8; 256];
+            let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+            let out = core::str::from_utf8(&buf[..n as usize]).unwrap();
+            let nl = crate::__LIBC_NEWLINE;
+            assert!(out.contains(&std::format!("] 1 + 1 = 2{}", nl)));
+            assert!(out.contains(&std::format!("] 1 = 1{}", nl)));
+            assert!(out.contains(&std::format!("] \"two\" = \"two\"{}", nl)));
+        }
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[test]
+    fn test_dbg_forms() {
+        libc_dbg!();
+        let (a, b) = libc_dbg!(1, "two",);
+        assert_eq!((a, b), (1, "two"));
+    }
+
+    #[test]
+    #[allow(clippy::let_unit_value, clippy::unnecessary_literal_unwrap)]
     fn test_in_closure_expression() {
         use super::std_name::*;
         // https://github.com/mmastrac/rust-libc-print/issues/86
@@ -0,0 +1,174 @@
+//! Per-descriptor spinlocks used by the `locked` feature to stop concurrent lines from
+//! interleaving.
+//!
+//! The locks only coordinate writers within this process that go through this crate. Each
+//! lock is for the descriptor a write actually goes to, after
+//! [`set_stdout_fd`](crate::set_stdout_fd) and [`set_stderr_fd`](crate::set_stderr_fd), so
+//! redirecting one standard stream to the other makes them share a lock. There are only 16
+//! locks, so some unrelated descriptors share one and take turns writing. They
+//! are not re-entrant: printing to a stream from inside a `Debug` or `Display` implementation
+//! that is itself being printed to the same stream will deadlock, as will printing from a
+//! signal handler that interrupted a write (use [`libc_raw_panic_write`](crate::libc_raw_panic_write)
+//! there instead).
+//!
+//! With the `critical-section` feature, each write is also made inside a critical section,
+//! which on single-core embedded targets typically disables interrupts, so a line printed from
+//! an interrupt handler can't land in the middle of one printed from the main loop. Only the
+//! write itself is made in the critical section. A line that fits in the format buffer goes out
+//! in one write, so stays whole, but longer output may be split between writes. On single-core
+//! targets, don't combine this with `locked`: an interrupt handler that prints while the main
+//! loop holds the spinlock would spin forever.
+
+#[cfg(feature = "locked")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of locks. Descriptors below this each get their own.
+#[cfg(feature = "locked")]
+const LOCKS: usize = 16;
+
+#[cfg(feature = "locked")]
+#[allow(clippy::declare_interior_mutable_const)]
+const UNLOCKED: AtomicBool = AtomicBool::new(false);
+#[cfg(feature = "locked")]
+static FD_LOCKS: [AtomicBool; LOCKS] = [UNLOCKED; LOCKS];
+
+/// Holds the lock for a descriptor until dropped. Without the `locked` feature this is a
+/// no-op.
+pub(crate) struct FdGuard {
+    #[cfg(feature = "locked")]
+    lock: &'static AtomicBool,
+}
+
+/// Blocks until the lock for `fd` is acquired.
+#[inline]
+pub(crate) fn lock(fd: i32) -> FdGuard {
+    #[cfg(feature = "locked")]
+    let lock = {
+        let fd = crate::redirect::resolve(fd);
+        let lock = &FD_LOCKS[fd as u32 as usize % LOCKS];
+        while lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        lock
+    };
+    FdGuard {
+        #[cfg(feature = "locked")]
+        lock,
+    }
+}
+
+#[cfg(feature = "locked")]
+impl Drop for FdGuard {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.store(false, Ordering::Release);
+    }
+}
+
+/// Runs `f`, inside a critical section with the `critical-section` feature.
+#[inline]
+pub(crate) fn critical<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "critical-section")]
+    return critical_section::with(|_| f());
+    #[cfg(not(feature = "critical-section"))]
+    f()
+}
+
+#[cfg(all(test, unix, any(feature = "locked", feature = "critical-section")))]
+mod tests {
+    use core::fmt::Write;
+    use std::{thread, vec, vec::Vec};
+
+    #[test]
+    fn test_guards_dropped_out_of_order() {
+        // Groups can be dropped in any order from safe code
+        let mut a = crate::stdout_group();
+        let mut b = crate::stderr_group();
+        write!(a, "first").unwrap();
+        write!(b, "second").unwrap();
+        drop(a);
+        drop(b);
+        crate::libc_println!(" still printing");
+    }
+
+    #[cfg(feature = "locked")]
+    #[test]
+    fn test_lock_follows_redirection() {
+        use super::{lock, FD_LOCKS};
+        use core::sync::atomic::Ordering;
+
+        let output =
+            crate::tests::run_in_child("lock::tests::test_lock_follows_redirection", || {
+                crate::set_stdout_fd(crate::__LIBC_STDERR);
+                let guard = lock(crate::__LIBC_STDOUT);
+                assert!(FD_LOCKS[crate::__LIBC_STDERR as usize].load(Ordering::Relaxed));
+                assert!(!FD_LOCKS[crate::__LIBC_STDOUT as usize].load(Ordering::Relaxed));
+                drop(guard);
+
+                // Descriptors that don't collide in the table can be held together
+                let a = lock(20);
+                let b = lock(21);
+                drop((a, b));
+            });
+        crate::tests::assert_success(&output);
+    }
+
+    // The critical section alone only covers each write, not a whole line
+    #[cfg(feature = "locked")]
+    #[test]
+    fn test_lines_do_not_interleave() {
+        const PART: usize = 400;
+        const LINES: usize = 200;
+        const THREADS: u8 = 4;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [rd, wr] = fds;
+
+        let reader = thread::spawn(move || {
+            let mut out = Vec::new();
+            let mut buf = vec![0_u8; 4096];
+            loop {
+                let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+                if n <= 0 {
+                    break;
+                }
+                out.extend_from_slice(&buf[..n as usize]);
+            }
+            out
+        });
+
+        // Each line is longer than the format buffer, so takes several writes
+        let writers: Vec<_> = (0..THREADS)
+            .map(|i| {
+                thread::spawn(move || {
+                    let part = [b'a' + i; PART];
+                    let p = core::str::from_utf8(&part).unwrap();
+                    for _ in 0..LINES {
+                        crate::libc_fprintln!(wr, "{}{}{}{}{}", p, p, p, p, p);
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        unsafe { libc::close(wr) };
+
+        let out = reader.join().unwrap();
+        let lines: Vec<_> = out
+            .split(|&b| b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| l.strip_suffix(b"\r").unwrap_or(l))
+            .collect();
+        assert_eq!(lines.len(), LINES * THREADS as usize);
+        for line in lines {
+            assert_eq!(line.len(), PART * 5);
+            assert!(line.iter().all(|&b| b == line[0]));
+        }
+        unsafe { libc::close(rd) };
+    }
+}
@@ -0,0 +1,66 @@
+//! A [`log`] backend that writes records through this crate, without requiring `std` or an
+//! allocator.
+//!
+//! Records at [`Level::Error`] and [`Level::Warn`] are written to the standard error, and
+//! everything else to the standard output, formatted as `[LEVEL target] message`.
+//!
+//! ```rust
+//! libc_print::logger::init_with_level(log::Level::Info).unwrap();
+//! log::info!("Hello {}!", "log");
+//! ```
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+
+/// A [`log::Log`] implementation that writes to the standard output and error.
+pub struct LibCLogger;
+
+static LOGGER: LibCLogger = LibCLogger;
+
+impl Log for LibCLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let fd = match record.level() {
+            Level::Error | Level::Warn => crate::__LIBC_STDERR,
+            _ => crate::__LIBC_STDOUT,
+        };
+        crate::libc_fprintln!(
+            fd,
+            "[{} {}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs [`LibCLogger`] as the global logger, logging records at all levels.
+pub fn init() -> Result<(), SetLoggerError> {
+    init_with_level(Level::Trace)
+}
+
+/// Installs [`LibCLogger`] as the global logger, logging records at `level` and above.
+pub fn init_with_level(level: Level) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level.to_level_filter());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_logger() {
+        // Only the first test to install a logger wins
+        let _ = super::init_with_level(log::Level::Info);
+        log::error!("error via log");
+        log::info!("info via {}", "log");
+        log::debug!("filtered out");
+    }
+}
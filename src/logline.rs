@@ -0,0 +1,65 @@
+//! Splitting output into the NUL-terminated messages that system loggers take.
+
+use core::ffi::c_char;
+
+/// The longest message passed to a system logger in one call. Loggers truncate long
+/// messages, so longer writes are split across several.
+const LOG_CHUNK: usize = 1024;
+
+/// Calls `f` with NUL-terminated copies of `bytes`, split into chunks that a system logger
+/// will accept whole.
+///
+/// Loggers end each message themselves, so a trailing newline is dropped, and nothing is
+/// logged for an empty line.
+pub(crate) fn for_each_message<E>(
+    bytes: &[u8],
+    mut f: impl FnMut(*const c_char) -> Result<(), E>,
+) -> Result<(), E> {
+    let line = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let mut buf = [0_u8; LOG_CHUNK + 1];
+    for chunk in line.chunks(LOG_CHUNK) {
+        buf[..chunk.len()].copy_from_slice(chunk);
+        buf[chunk.len()] = 0;
+        f(buf.as_ptr().cast())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ffi::CStr;
+    use std::vec::Vec;
+
+    fn messages(bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        for_each_message::<()>(bytes, |msg| {
+            out.push(unsafe { CStr::from_ptr(msg) }.to_bytes().to_vec());
+            Ok(())
+        })
+        .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_for_each_message() {
+        assert_eq!(messages(b"hello\n"), [b"hello"]);
+        assert_eq!(messages(b"hello\r\n"), [b"hello"]);
+        assert_eq!(messages(b"no newline"), [b"no newline"]);
+        assert!(messages(b"\n").is_empty());
+
+        let long = [b'x'; LOG_CHUNK + 10];
+        let split = messages(&long);
+        assert_eq!(split.len(), 2);
+        assert_eq!((split[0].len(), split[1].len()), (LOG_CHUNK, 10));
+
+        // Stops at the first error
+        let mut calls = 0;
+        let res = for_each_message(&long, |_| {
+            calls += 1;
+            Err(5)
+        });
+        assert_eq!((res, calls), (Err(5), 1));
+    }
+}
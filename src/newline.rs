@@ -0,0 +1,84 @@
+use core::fmt::{self, Debug, Display, Write};
+
+/// Wraps a value passed to `libc_dbg!` so that, with the `crlf` feature, the newlines in its
+/// pretty-printed `Debug` output, or its `Display` output for `libc_dbg_display!`, match
+/// [`__LIBC_NEWLINE`](crate::__LIBC_NEWLINE).
+#[doc(hidden)]
+pub struct __LibCDbgValue<'a, T: ?Sized>(pub &'a T);
+
+impl<T: Debug + ?Sized> Debug for __LibCDbgValue<'_, T> {
+    #[cfg(not(feature = "crlf"))]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+
+    #[cfg(feature = "crlf")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut w = CrlfWriter(f);
+        if w.0.alternate() {
+            write!(w, "{:#?}", self.0)
+        } else {
+            write!(w, "{:?}", self.0)
+        }
+    }
+}
+
+impl<T: Display + ?Sized> Display for __LibCDbgValue<'_, T> {
+    #[cfg(not(feature = "crlf"))]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+
+    #[cfg(feature = "crlf")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(CrlfWriter(f), "{}", self.0)
+    }
+}
+
+/// Replaces each `\n` written through it with `\r\n`.
+struct CrlfWriter<W>(W);
+
+impl<W: Write> Write for CrlfWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut lines = s.split('\n');
+        if let Some(first) = lines.next() {
+            self.0.write_str(first)?;
+        }
+        for line in lines {
+            self.0.write_str("\r\n")?;
+            self.0.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_crlf_writer() {
+        let mut w = CrlfWriter(String::new());
+        write!(w, "{:#?}", [1, 2]).unwrap();
+        assert_eq!(w.0, "[\r\n    1,\r\n    2,\r\n]");
+    }
+
+    #[test]
+    fn test_dbg_value() {
+        let s = std::format!(
+            "{:?} {:#?}",
+            __LibCDbgValue(&(1, "a")),
+            __LibCDbgValue(&[3])
+        );
+        assert_eq!(
+            s,
+            std::format!("{:?} {:#?}", (1, "a"), [3]).replace('\n', crate::__LIBC_NEWLINE)
+        );
+
+        let s = std::format!("{}", __LibCDbgValue("two\nlines"));
+        assert_eq!(s, std::format!("two{}lines", crate::__LIBC_NEWLINE));
+    }
+}
@@ -0,0 +1,87 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Macro for printing to the standard output, with a newline, only the first time this call
+/// site is reached.
+///
+/// ```rust
+/// # use libc_print::libc_println_once;
+/// for i in 0..3 {
+///     libc_println_once!("first pass: {}", i);
+///     //  ^-- prints once: first pass: 0
+/// }
+/// ```
+///
+/// Each call site is tracked separately, with a `static` flag, so this can be used from hot
+/// loops and interrupt handlers without allocating.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_println_once {
+    ($($arg:tt)*) => {
+        {
+            static ONCE: $crate::__LibCOnce = $crate::__LibCOnce::new();
+            if ONCE.first() {
+                $crate::libc_println!($($arg)*);
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, only the first time this call
+/// site is reached.
+///
+/// See [`libc_println_once`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_eprintln_once {
+    ($($arg:tt)*) => {
+        {
+            static ONCE: $crate::__LibCOnce = $crate::__LibCOnce::new();
+            if ONCE.first() {
+                $crate::libc_eprintln!($($arg)*);
+            }
+        }
+    };
+}
+
+/// A flag that is only set once, by whichever caller gets there first.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct __LibCOnce(AtomicBool);
+
+impl __LibCOnce {
+    #[inline]
+    pub const fn new() -> __LibCOnce {
+        __LibCOnce(AtomicBool::new(false))
+    }
+
+    /// Returns true the first time it's called, and false every time after.
+    #[inline]
+    pub fn first(&self) -> bool {
+        // Checking first keeps later calls from contending for the cache line
+        !self.0.load(Ordering::Relaxed) && !self.0.swap(true, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_once() {
+        let once = __LibCOnce::new();
+        assert!(once.first());
+        assert!(!once.first());
+
+        let mut evaluated = 0;
+        for _ in 0..3 {
+            libc_println_once!("once {}", {
+                evaluated += 1;
+                "stdout"
+            });
+            libc_eprintln_once!("once {}", "stderr");
+        }
+        assert_eq!(evaluated, 1);
+    }
+}
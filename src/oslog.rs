@@ -0,0 +1,75 @@
+//! Apple unified logging support.
+//!
+//! Daemons and code loaded into GUI apps often have their standard error connected to
+//! nothing. With the `os-log` feature, everything written to the standard output and error is
+//! also sent to the unified log, where it shows up in Console.app and `log stream`: the
+//! standard output with the default type, and the standard error with the error type.
+//!
+//! `os_log_with_type` is a C macro, so this calls the `_os_log_impl` function it expands to,
+//! passing the message as a single public `%s` argument.
+
+use core::ffi::{c_char, c_void};
+
+const OS_LOG_TYPE_DEFAULT: u8 = 0x00;
+const OS_LOG_TYPE_ERROR: u8 = 0x10;
+
+/// The argument block header: one argument, which is not a scalar.
+const HDR_HAS_NON_SCALAR: u8 = 0x02;
+/// The argument descriptor: a public string, passed as a pointer.
+const ARG_PUBLIC_STRING: u8 = 0x22;
+const PTR_SIZE: usize = core::mem::size_of::<usize>();
+
+extern "C" {
+    static __dso_handle: c_void;
+    static _os_log_default: c_void;
+    fn os_log_type_enabled(log: *const c_void, ty: u8) -> bool;
+    fn _os_log_impl(
+        dso: *const c_void,
+        log: *const c_void,
+        ty: u8,
+        format: *const c_char,
+        buf: *mut u8,
+        size: u32,
+    );
+}
+
+// The log only records the offset of the format string, which it looks up in this section
+// of the binary when the message is displayed.
+#[link_section = "__TEXT,__oslogstring,cstring_literals"]
+static FORMAT: [u8; 11] = *b"%{public}s\0";
+
+/// Sends a copy of `bytes` to the unified log if `fd` is the standard output or error.
+///
+/// The unified log ends each entry itself, so a trailing newline is dropped.
+pub(crate) fn write_log(fd: i32, bytes: &[u8]) {
+    let ty = match fd {
+        crate::__LIBC_STDOUT => OS_LOG_TYPE_DEFAULT,
+        crate::__LIBC_STDERR => OS_LOG_TYPE_ERROR,
+        _ => return,
+    };
+    // SAFETY: `_os_log_default` is the `OS_LOG_DEFAULT` log object
+    let log = unsafe { &_os_log_default as *const c_void };
+    // SAFETY: `log` is a valid log object
+    if !unsafe { os_log_type_enabled(log, ty) } {
+        return;
+    }
+
+    let _ = crate::logline::for_each_message::<()>(bytes, |msg| {
+        let mut args = [0_u8; 4 + PTR_SIZE];
+        args[..4].copy_from_slice(&[HDR_HAS_NON_SCALAR, 1, ARG_PUBLIC_STRING, PTR_SIZE as u8]);
+        args[4..].copy_from_slice(&(msg as usize).to_ne_bytes());
+        // SAFETY: `FORMAT` takes one string argument, which `args` describes and points to
+        // the NUL-terminated `msg`
+        unsafe {
+            _os_log_impl(
+                &__dso_handle,
+                log,
+                ty,
+                FORMAT.as_ptr().cast(),
+                args.as_mut_ptr(),
+                args.len() as u32,
+            );
+        }
+        Ok(())
+    });
+}
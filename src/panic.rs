@@ -0,0 +1,192 @@
+use core::fmt::{Arguments, Display, Write};
+use core::panic::{Location, PanicInfo};
+
+/// Defines a `#[panic_handler]` that prints the panic message and location to the standard
+/// error using [`libc_print_panic`], then aborts the process.
+///
+/// This is only useful in `#![no_std]` binaries, which must provide their own panic handler.
+///
+/// ```rust,ignore
+/// #![no_std]
+/// #![no_main]
+///
+/// libc_print::libc_panic_handler!();
+/// ```
+///
+/// Alternatively, enable the `panic-handler` feature to have this crate define the handler
+/// when built with `panic = "abort"`.
+#[macro_export]
+macro_rules! libc_panic_handler {
+    () => {
+        #[panic_handler]
+        fn __libc_print_panic_handler(info: &core::panic::PanicInfo) -> ! {
+            $crate::libc_print_panic(info);
+            $crate::__libc_abort()
+        }
+    };
+}
+
+/// Prints a message to the standard error and aborts the process, without going through the
+/// panic machinery.
+///
+/// The message is printed in the same format as a panic, with the caller's location:
+///
+/// ```rust,no_run
+/// # use libc_print::libc_panic;
+/// let fd = -1;
+/// if fd < 0 {
+///     libc_panic!("can't open the log: {}", fd);
+///     //  ^-- prints: panicked at src/main.rs:3:5:
+///     //              can't open the log: -1
+/// }
+/// ```
+///
+/// Anything buffered by the C library is flushed first, so it isn't lost when the process
+/// aborts. This is useful in `panic = "abort"` binaries that can't afford the code size of a
+/// panic handler that formats messages.
+#[macro_export]
+macro_rules! libc_panic {
+    () => {
+        $crate::libc_panic!("explicit panic")
+    };
+    ($($arg:tt)+) => {
+        $crate::__libc_panic(format_args!($($arg)+))
+    };
+}
+
+#[cfg(all(feature = "panic-handler", feature = "std"))]
+compile_error!("the `panic-handler` feature defines a panic handler, so can't be used with `std`");
+
+// Installs the handler for the whole program. `#![no_std]` binaries must be built with
+// `panic = "abort"` anyway, and leaving it out of unwinding builds keeps it away from the
+// tests and doctests, which link `std` and so have a handler already.
+#[cfg(all(feature = "panic-handler", panic = "abort", not(test)))]
+crate::libc_panic_handler!();
+
+/// Prints a panic's message and location to the standard error, in the same format as the
+/// standard library's default panic hook.
+///
+/// This is intended to be called from a `#[panic_handler]`, and does not allocate:
+///
+/// ```rust,ignore
+/// #[panic_handler]
+/// fn panic(info: &core::panic::PanicInfo) -> ! {
+///     libc_print::libc_print_panic(info);
+///     loop {}
+/// }
+/// ```
+pub fn libc_print_panic(info: &PanicInfo) {
+    let mut stm = crate::__LibCBufWriter::new(crate::__LIBC_STDERR);
+    let _ = write_panic(&mut stm, info.location(), info.message());
+}
+
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __libc_panic(message: Arguments) -> ! {
+    {
+        let mut stm = crate::__LibCBufWriter::new(crate::__LIBC_STDERR);
+        let _ = write_panic(&mut stm, Some(Location::caller()), message);
+    }
+    crate::sync_with_stdio();
+    __libc_abort()
+}
+
+pub(crate) fn write_panic<W: Write>(
+    w: &mut W,
+    location: Option<&Location>,
+    message: impl Display,
+) -> core::fmt::Result {
+    match location {
+        Some(location) => write!(w, "panicked at {}:", location)?,
+        None => w.write_str("panicked:")?,
+    }
+    w.write_str(crate::__LIBC_NEWLINE)?;
+    write!(w, "{}", message)?;
+    w.write_str(crate::__LIBC_NEWLINE)
+}
+
+/// Called when a write fails after any retries. With the `abort-on-error` feature, this
+/// reports the failure on the standard error (unless that's what failed) and aborts.
+#[inline]
+pub(crate) fn write_failed(handle: i32) {
+    #[cfg(feature = "abort-on-error")]
+    {
+        if handle != crate::__LIBC_STDERR {
+            crate::__libc_raw_write(
+                crate::__LIBC_STDERR,
+                b"libc-print: write failed, aborting\n",
+            );
+        }
+        __libc_abort();
+    }
+}
+
+/// Terminates the process abnormally, without unwinding.
+#[doc(hidden)]
+pub fn __libc_abort() -> ! {
+    #[cfg(not(target_os = "wasi"))]
+    // SAFETY: `abort` has no preconditions
+    unsafe {
+        crate::sys::abort()
+    }
+    #[cfg(target_os = "wasi")]
+    core::arch::wasm32::unreachable()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_panic_format() {
+        let mut s = String::new();
+        let location = Location::caller();
+        write_panic(&mut s, Some(location), format_args!("oh no: {}", 42)).unwrap();
+        let nl = crate::__LIBC_NEWLINE;
+        assert_eq!(
+            s,
+            std::format!(
+                "panicked at {}:{}:{}:{}oh no: 42{}",
+                file!(),
+                location.line(),
+                location.column(),
+                nl,
+                nl
+            )
+        );
+
+        let mut s = String::new();
+        write_panic(&mut s, None, "no location").unwrap();
+        assert_eq!(s, std::format!("panicked:{}no location{}", nl, nl));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_libc_panic_aborts() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = crate::tests::run_in_child("panic::tests::test_libc_panic_aborts", || {
+            crate::libc_panic!("giving up after {} tries", 3);
+        });
+        assert_eq!(output.status.signal(), Some(libc::SIGABRT));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("panicked at src/panic.rs:"));
+        let nl = crate::__LIBC_NEWLINE;
+        assert!(stderr.contains(&std::format!(":{}giving up after 3 tries{}", nl, nl)));
+    }
+
+    #[cfg(all(unix, feature = "abort-on-error"))]
+    #[test]
+    fn test_abort_on_error() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = crate::tests::run_in_child("panic::tests::test_abort_on_error", || {
+            crate::libc_fprintln!(-1, "nowhere");
+        });
+        assert_eq!(output.status.signal(), Some(libc::SIGABRT));
+        let stderr = std::string::String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("libc-print: write failed, aborting"));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::fast::{format_u64, INT_BUFFER};
+use core::cell::UnsafeCell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A tag written at the start of every line, set with [`set_line_prefix`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefix {
+    /// No prefix. This is the default.
+    None,
+    /// The process id, as `[<pid>] `.
+    Pid,
+    /// The id of the thread printing the line, as `[<tid>] `.
+    Tid,
+    /// A fixed tag, as `[<tag>] `.
+    Static(&'static str),
+}
+
+/// The current prefix, behind a spinlock, as it can't be swapped atomically.
+struct PrefixCell {
+    locked: AtomicBool,
+    prefix: UnsafeCell<Prefix>,
+}
+
+// SAFETY: `prefix` is only accessed while `locked` is held
+unsafe impl Sync for PrefixCell {}
+
+impl PrefixCell {
+    fn with<R>(&self, f: impl FnOnce(&mut Prefix) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // SAFETY: we hold the lock, so have exclusive access
+        let res = f(unsafe { &mut *self.prefix.get() });
+        self.locked.store(false, Ordering::Release);
+        res
+    }
+}
+
+static PREFIX: PrefixCell = PrefixCell {
+    locked: AtomicBool::new(false),
+    prefix: UnsafeCell::new(Prefix::None),
+};
+
+/// Lets lines skip the lock when there's no prefix.
+static HAS_PREFIX: AtomicBool = AtomicBool::new(false);
+
+/// Sets the tag written at the start of every line printed by the line-oriented macros, such
+/// as the `println`, `writeln`, `puts`, `fastln`, `uwriteln`, `wrapln` and `base64ln`
+/// families, returning the previous one.
+///
+/// ```rust
+/// # use libc_print::{libc_println, set_line_prefix, Prefix};
+/// set_line_prefix(Prefix::Static("myapp"));
+/// libc_println!("started");
+/// //  ^-- prints: [myapp] started
+/// set_line_prefix(Prefix::Pid);
+/// libc_println!("started");
+/// //  ^-- prints: [1234] started
+/// # set_line_prefix(Prefix::None);
+/// ```
+///
+/// Only the first line of a word-wrapped message is tagged. Output that isn't a single line
+/// of text is left untagged: the `print` and `write` macros without a newline, multi-line
+/// blocks such as [`libc_hexdump`](crate::libc_hexdump), [`libc_hist`](crate::libc_hist) and
+/// [`libc_print_error_chain`](crate::libc_print_error_chain), [`libc_json`](crate::libc_json),
+/// whose lines must stay valid JSON, panic and assertion messages, and the `_sigsafe` macros.
+///
+/// The setting is process-wide. On WASI and with the `semihosting` feature, where there are no
+/// process or thread ids, they are printed as `0`, as are thread ids on Hermit.
+pub fn set_line_prefix(prefix: Prefix) -> Prefix {
+    PREFIX.with(|current| {
+        HAS_PREFIX.store(prefix != Prefix::None, Ordering::Relaxed);
+        core::mem::replace(current, prefix)
+    })
+}
+
+/// Writes the prefix, if any, that starts every line printed by the line-oriented macros.
+#[doc(hidden)]
+#[inline]
+pub fn __libc_line_prefix<W: Write>(w: &mut W) -> core::fmt::Result {
+    #[cfg(feature = "timestamp")]
+    if crate::timestamp::enabled() {
+        crate::__libc_timestamp(w)?;
+    }
+    write_current_prefix(w)
+}
+
+/// Writes the prefix that starts every line printed by `libc_tprintln!`, which always has a
+/// timestamp, whether or not [`set_timestamps`](crate::set_timestamps) is enabled.
+#[cfg(feature = "timestamp")]
+#[doc(hidden)]
+#[inline]
+pub fn __libc_tline_prefix<W: Write>(w: &mut W) -> core::fmt::Result {
+    crate::__libc_timestamp(w)?;
+    write_current_prefix(w)
+}
+
+fn write_current_prefix<W: Write>(w: &mut W) -> core::fmt::Result {
+    if HAS_PREFIX.load(Ordering::Relaxed) {
+        write_prefix(w, PREFIX.with(|prefix| *prefix))?;
+    }
+    Ok(())
+}
+
+fn write_prefix<W: Write>(w: &mut W, prefix: Prefix) -> core::fmt::Result {
+    let mut buf = [0; INT_BUFFER];
+    let tag = match prefix {
+        Prefix::None => return Ok(()),
+        Prefix::Pid => format_u64(&mut buf, pid()),
+        Prefix::Tid => format_u64(&mut buf, tid()),
+        Prefix::Static(tag) => tag,
+    };
+    w.write_str("[")?;
+    w.write_str(tag)?;
+    w.write_str("] ")
+}
+
+/// Returns the id of the current process.
+fn pid() -> u64 {
+    #[cfg(not(any(target_os = "wasi", feature = "semihosting")))]
+    // SAFETY: `getpid` has no preconditions
+    return unsafe { crate::sys::getpid() } as u64;
+
+    #[allow(unreachable_code)]
+    0
+}
+
+/// Returns the id of the calling thread.
+fn tid() -> u64 {
+    // SAFETY: each of these has no preconditions, or only writes to `id`
+    unsafe {
+        #[cfg(all(target_os = "linux", not(feature = "no-libc")))]
+        return libc::syscall(libc::SYS_gettid) as u64;
+        #[cfg(all(target_os = "linux", feature = "no-libc"))]
+        return crate::syscall::gettid() as u64;
+        #[cfg(target_os = "android")]
+        return libc::gettid() as u64;
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        {
+            let mut id = 0;
+            libc::pthread_threadid_np(libc::pthread_self(), &mut id);
+            return id;
+        }
+        #[cfg(all(windows, not(feature = "semihosting")))]
+        return crate::windows::current_thread_id();
+        #[cfg(all(
+            unix,
+            not(any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "macos",
+                target_os = "ios"
+            ))
+        ))]
+        return libc::pthread_self() as usize as u64;
+    }
+
+    #[allow(unreachable_code)]
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_write_prefix() {
+        let mut s = String::new();
+        write_prefix(&mut s, Prefix::Static("myapp")).unwrap();
+        write_prefix(&mut s, Prefix::None).unwrap();
+        assert_eq!(s, "[myapp] ");
+
+        let mut s = String::new();
+        write_prefix(&mut s, Prefix::Pid).unwrap();
+        assert_eq!(s, std::format!("[{}] ", std::process::id()));
+
+        let mut s = String::new();
+        write_prefix(&mut s, Prefix::Tid).unwrap();
+        assert!(s.starts_with('[') && s.ends_with("] "), "{:?}", s);
+    }
+
+    #[test]
+    fn test_set_line_prefix() {
+        // Other tests' output would be affected
+        let output = crate::tests::run_in_child("prefix::tests::test_set_line_prefix", || {
+            assert_eq!(set_line_prefix(Prefix::Static("tagged")), Prefix::None);
+            crate::libc_println!("line");
+            crate::libc_writeln!("static");
+            crate::libc_fastln!("fast ", 1_u32);
+            crate::libc_println_styled!(crate::Color::Green, "styled");
+            crate::libc_wrapln!(40, "wrapped");
+            crate::libc_base64ln!(b"hi");
+            assert_eq!(set_line_prefix(Prefix::None), Prefix::Static("tagged"));
+            crate::libc_println!("plain");
+        });
+        crate::tests::assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let nl = crate::__LIBC_NEWLINE;
+        assert!(
+            stdout.contains(&std::format!(
+                "[tagged] line{}[tagged] static{}[tagged] fast 1{}[tagged] styled{}\
+                 [tagged] wrapped{}[tagged] aGk={}plain{}",
+                nl,
+                nl,
+                nl,
+                nl,
+                nl,
+                nl,
+                nl
+            )),
+            "{:?}",
+            stdout
+        );
+    }
+}
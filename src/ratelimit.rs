@@ -0,0 +1,142 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Macro for printing to the standard output, with a newline, at most `max_per_sec` times a
+/// second from this call site.
+///
+/// Lines beyond the limit are dropped without being formatted. Once a line gets through
+/// again, it's preceded by a count of the lines that were dropped:
+///
+/// ```rust
+/// # use libc_print::libc_println_ratelimited;
+/// for packet in 0..1000 {
+///     libc_println_ratelimited!(2, "bad checksum in packet {}", packet);
+/// }
+/// //  ^-- prints: bad checksum in packet 0
+/// //              bad checksum in packet 1
+/// // and a second or more later, the next time a line gets through:
+/// //              (998 messages suppressed)
+/// ```
+///
+/// Each call site is tracked separately, with `static` counters, and checking the limit costs
+/// a read of the monotonic clock, so this can be used from packet and event processing loops
+/// where printing every line would destroy throughput.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_println_ratelimited {
+    ($max_per_sec:expr, $($arg:tt)*) => {
+        $crate::__libc_fprintln_ratelimited!($crate::__LIBC_STDOUT, $max_per_sec, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, at most `max_per_sec` times a
+/// second from this call site.
+///
+/// See [`libc_println_ratelimited`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_eprintln_ratelimited {
+    ($max_per_sec:expr, $($arg:tt)*) => {
+        $crate::__libc_fprintln_ratelimited!($crate::__LIBC_STDERR, $max_per_sec, $($arg)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_fprintln_ratelimited {
+    ($fd:expr, $max_per_sec:expr $(,)?) => {
+        $crate::__libc_fprintln_ratelimited!($fd, $max_per_sec, "")
+    };
+    ($fd:expr, $max_per_sec:expr, $($arg:tt)*) => {
+        {
+            static LIMIT: $crate::__LibCRateLimit = $crate::__LibCRateLimit::new();
+            if let Some(suppressed) = LIMIT.check($max_per_sec) {
+                if suppressed > 0 {
+                    $crate::libc_fprintln!($fd, "({} messages suppressed)", suppressed);
+                }
+                $crate::libc_fprintln!($fd, $($arg)*);
+            }
+        }
+    };
+}
+
+/// Counts the lines printed from a call site in the current second of the monotonic clock.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct __LibCRateLimit {
+    /// The second being counted, truncated to 32 bits so it fits in an atomic everywhere.
+    second: AtomicU32,
+    /// The lines printed so far this second.
+    printed: AtomicU32,
+    /// The lines dropped since the last one was printed.
+    suppressed: AtomicU32,
+}
+
+impl __LibCRateLimit {
+    #[inline]
+    pub const fn new() -> __LibCRateLimit {
+        __LibCRateLimit {
+            second: AtomicU32::new(0),
+            printed: AtomicU32::new(0),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns `Some` with the number of lines dropped since the last one that was printed if
+    /// this line is within `max_per_sec`, or `None` if it should be dropped.
+    #[inline]
+    pub fn check(&self, max_per_sec: u32) -> Option<u32> {
+        self.check_at(crate::clock::monotonic_now().0 as u32, max_per_sec)
+    }
+
+    fn check_at(&self, now: u32, max_per_sec: u32) -> Option<u32> {
+        let second = self.second.load(Ordering::Relaxed);
+        if second != now
+            && self
+                .second
+                .compare_exchange(second, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.printed.store(0, Ordering::Relaxed);
+        }
+        if self.printed.fetch_add(1, Ordering::Relaxed) < max_per_sec {
+            Some(self.suppressed.swap(0, Ordering::Relaxed))
+        } else {
+            // Keep the count from wrapping around to zero during a long burst
+            self.printed.store(max_per_sec, Ordering::Relaxed);
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        let limit = __LibCRateLimit::new();
+        assert_eq!(limit.check_at(5, 2), Some(0));
+        assert_eq!(limit.check_at(5, 2), Some(0));
+        assert_eq!(limit.check_at(5, 2), None);
+        assert_eq!(limit.check_at(5, 2), None);
+        // The next second lets lines through again, reporting what was dropped
+        assert_eq!(limit.check_at(6, 2), Some(2));
+        assert_eq!(limit.check_at(6, 2), Some(0));
+        assert_eq!(limit.check_at(6, 2), None);
+        assert_eq!(limit.check_at(9, 2), Some(1));
+
+        let mut evaluated = 0;
+        for _ in 0..3 {
+            libc_println_ratelimited!(1, "ratelimited {}", {
+                evaluated += 1;
+                "stdout"
+            });
+            libc_eprintln_ratelimited!(1, "ratelimited {}", "stderr");
+        }
+        // A second boundary may fall inside the loop
+        assert!((1..=2).contains(&evaluated));
+    }
+}
@@ -0,0 +1,237 @@
+use core::convert::TryFrom;
+
+/// Macro for reading a line from the standard input into a byte buffer, returning it as a
+/// `Result<&str, ReadLineError>` without the line ending.
+///
+/// An optional prompt, with format arguments, is printed to the standard output and flushed
+/// first:
+///
+/// ```rust,no_run
+/// # use libc_print::{libc_println, libc_readln};
+/// let mut buf = [0_u8; 64];
+/// if let Ok(name) = libc_readln!(&mut buf, "name? ") {
+///     libc_println!("hello, {}", name);
+/// }
+/// ```
+///
+/// See [`read_line_into`] for details.
+#[macro_export]
+macro_rules! libc_readln {
+    ($buf:expr $(,)?) => {
+        $crate::read_line_into($crate::__LIBC_STDIN, $buf)
+    };
+    ($buf:expr, $($arg:tt)+) => {
+        {
+            $crate::libc_print!($($arg)+);
+            // With the `c-stdio` feature, the prompt would otherwise sit in the C library's
+            // buffer while the read blocks
+            $crate::libc_flush!();
+            $crate::read_line_into($crate::__LIBC_STDIN, $buf)
+        }
+    };
+}
+
+/// Why [`read_line_into`] couldn't return a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadLineError {
+    /// The input ended before anything was read.
+    Eof,
+    /// The buffer filled up before the end of the line. The rest of the line is left to be
+    /// read by the next call.
+    TooLong,
+    /// The line wasn't valid UTF-8.
+    InvalidUtf8,
+    /// Reading failed with the given OS error code.
+    Os(i32),
+}
+
+impl core::fmt::Display for ReadLineError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReadLineError::Eof => f.write_str("end of input"),
+            ReadLineError::TooLong => f.write_str("line too long for the buffer"),
+            ReadLineError::InvalidUtf8 => f.write_str("line is not valid UTF-8"),
+            ReadLineError::Os(err) => write!(f, "read failed with error {}", err),
+        }
+    }
+}
+
+/// Reads a line from `fd` into `buf`, returning it without the `\n` or `\r\n` that ended it.
+///
+/// ```rust,no_run
+/// use libc_print::{libc_println, read_line_into, ReadLineError};
+///
+/// let mut buf = [0_u8; 256];
+/// loop {
+///     match read_line_into(0, &mut buf) {
+///         Ok(line) => libc_println!("> {}", line),
+///         Err(ReadLineError::TooLong) => continue,
+///         Err(_) => break,
+///     }
+/// }
+/// ```
+///
+/// The line, including its ending, must fit in `buf`. A last line with no ending is returned
+/// as is, once the input ends. Reads are made a byte at a time, so nothing after the line is
+/// consumed, and the rest of the input can still be read by other code. Reads interrupted by
+/// a signal are retried.
+///
+/// Not available with the `semihosting` feature, or on Windows with the `no-libc` feature.
+pub fn read_line_into(fd: i32, buf: &mut [u8]) -> Result<&str, ReadLineError> {
+    let mut len = 0;
+    loop {
+        if len == buf.len() {
+            return Err(ReadLineError::TooLong);
+        }
+        // SAFETY: the platform read primitives accept any descriptor value
+        match unsafe { libc_read(fd, &mut buf[len..len + 1]) } {
+            Ok(0) if len == 0 => return Err(ReadLineError::Eof),
+            Ok(0) => break,
+            Ok(_) if buf[len] == b'\n' => break,
+            Ok(_) => len += 1,
+            Err(crate::sys::EINTR) => continue,
+            Err(err) => return Err(ReadLineError::Os(err)),
+        }
+    }
+
+    let line = match &buf[..len] {
+        [line @ .., b'\r'] => line,
+        line => line,
+    };
+    core::str::from_utf8(line).map_err(|_| ReadLineError::InvalidUtf8)
+}
+
+/// Returns the number of bytes read, or the OS error code on failure.
+#[cfg(not(any(
+    windows,
+    target_os = "wasi",
+    target_os = "hermit",
+    all(feature = "no-libc", target_os = "linux")
+)))]
+unsafe fn libc_read(fd: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+    usize::try_from(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) })
+        .map_err(|_| crate::errno())
+}
+
+/// Returns the number of bytes read, or the OS error code on failure.
+#[cfg(windows)]
+unsafe fn libc_read(fd: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    let len = libc::c_uint::try_from(buf.len()).unwrap_or(libc::c_uint::MAX);
+    // SAFETY: `buf` is valid for writes of at least `len` bytes
+    usize::try_from(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), len) })
+        .map_err(|_| crate::errno())
+}
+
+/// Returns the number of bytes read, or the WASI error code on failure.
+#[cfg(target_os = "wasi")]
+unsafe fn libc_read(fd: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    #[repr(C)]
+    struct Iovec {
+        buf: *mut u8,
+        buf_len: usize,
+    }
+
+    #[link(wasm_import_module = "wasi_snapshot_preview1")]
+    extern "C" {
+        #[link_name = "fd_read"]
+        fn wasi_fd_read(fd: i32, iovs: *const Iovec, iovs_len: usize, nread: *mut usize) -> i32;
+    }
+
+    let iov = Iovec {
+        buf: buf.as_mut_ptr(),
+        buf_len: buf.len(),
+    };
+    let mut read = 0;
+    // SAFETY: `iov` describes `buf`, which is valid for writes
+    match unsafe { wasi_fd_read(fd, &iov, 1, &mut read) } {
+        0 => Ok(read),
+        errno => Err(errno),
+    }
+}
+
+#[cfg(target_os = "hermit")]
+use crate::hermit::libc_read;
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+use crate::syscall::libc_read;
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_read_line_into() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let input = b"first\r\nsecond\n\xff\ntoo long\nlast";
+        assert_eq!(unsafe { crate::libc_write(fds[1], input) }, Ok(input.len()));
+        unsafe { libc::close(fds[1]) };
+
+        let mut buf = [0_u8; 8];
+        assert_eq!(read_line_into(fds[0], &mut buf), Ok("first"));
+        assert_eq!(read_line_into(fds[0], &mut buf), Ok("second"));
+        assert_eq!(
+            read_line_into(fds[0], &mut buf),
+            Err(ReadLineError::InvalidUtf8)
+        );
+        assert_eq!(
+            read_line_into(fds[0], &mut buf),
+            Err(ReadLineError::TooLong)
+        );
+        // The rest of the line is still there
+        assert_eq!(read_line_into(fds[0], &mut buf), Ok(""));
+        assert_eq!(read_line_into(fds[0], &mut buf), Ok("last"));
+        assert_eq!(read_line_into(fds[0], &mut buf), Err(ReadLineError::Eof));
+
+        assert_eq!(
+            read_line_into(-1, &mut buf),
+            Err(ReadLineError::Os(libc::EBADF))
+        );
+        unsafe { libc::close(fds[0]) };
+    }
+
+    #[test]
+    fn test_readln_prompt() {
+        use std::io::{Read, Write};
+        use std::os::unix::io::AsRawFd;
+        use std::process::Stdio;
+
+        const NAME: &str = "read::tests::test_readln_prompt";
+        if crate::tests::in_child(NAME) {
+            let mut buf = [0_u8; 16];
+            let name = crate::libc_readln!(&mut buf, "name? ").unwrap();
+            crate::libc_println!("hello {}", name);
+            return;
+        }
+
+        let mut child = crate::tests::child_command(NAME)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+
+        // The prompt must arrive while the child is waiting for input, even with the
+        // `c-stdio` feature buffering the standard output
+        let mut out = std::vec::Vec::new();
+        while !String::from_utf8_lossy(&out).contains("name? ") {
+            let mut fd = libc::pollfd {
+                fd: stdout.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            assert_eq!(unsafe { libc::poll(&mut fd, 1, 5000) }, 1, "no prompt");
+            let mut chunk = [0_u8; 256];
+            let n = stdout.read(&mut chunk).unwrap();
+            assert!(n > 0, "no prompt");
+            out.extend_from_slice(&chunk[..n]);
+        }
+
+        child.stdin.take().unwrap().write_all(b"bob\n").unwrap();
+        stdout.read_to_end(&mut out).unwrap();
+        assert!(child.wait().unwrap().success());
+        let out = String::from_utf8_lossy(&out);
+        assert!(out.contains("hello bob"), "{:?}", out);
+    }
+}
@@ -0,0 +1,184 @@
+use core::sync::atomic::{AtomicI32, AtomicPtr, Ordering};
+
+static STDOUT_FD: AtomicI32 = AtomicI32::new(crate::__LIBC_STDOUT);
+static STDERR_FD: AtomicI32 = AtomicI32::new(crate::__LIBC_STDERR);
+static OUTPUT_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The destination of bytes passed to an [`OutputHook`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+    /// Any other file descriptor.
+    Fd(i32),
+}
+
+impl Stream {
+    fn from_fd(fd: i32) -> Stream {
+        match fd {
+            crate::__LIBC_STDOUT => Stream::Stdout,
+            crate::__LIBC_STDERR => Stream::Stderr,
+            fd => Stream::Fd(fd),
+        }
+    }
+}
+
+/// A function that receives everything this crate writes, instead of it being written to a
+/// file descriptor.
+pub type OutputHook = fn(&[u8], Stream);
+
+/// Sends everything this crate writes to the standard output to `fd` instead, returning the
+/// descriptor it was previously sent to.
+///
+/// This is mostly useful for capturing output in tests: point the standard output at the
+/// write end of a pipe, run the code under test, then restore the previous descriptor and
+/// read back what was printed.
+///
+/// ```rust
+/// # use libc_print::{libc_println, set_stdout_fd};
+/// let previous = set_stdout_fd(2);
+/// libc_println!("this goes to stderr");
+/// set_stdout_fd(previous);
+/// ```
+///
+/// The redirection is process-wide and is applied when bytes are written, so it also affects
+/// writers created before the call. Raw writes made with [`libc_raw_panic_write`] are never
+/// redirected.
+///
+/// [`libc_raw_panic_write`]: crate::libc_raw_panic_write
+pub fn set_stdout_fd(fd: i32) -> i32 {
+    STDOUT_FD.swap(fd, Ordering::Relaxed)
+}
+
+/// Sends everything this crate writes to the standard error to `fd` instead, returning the
+/// descriptor it was previously sent to.
+///
+/// See [`set_stdout_fd`] for details.
+pub fn set_stderr_fd(fd: i32) -> i32 {
+    STDERR_FD.swap(fd, Ordering::Relaxed)
+}
+
+/// Sends everything this crate writes to `hook` rather than to the file descriptors, or
+/// restores normal output if `hook` is `None`. Returns the previously installed hook.
+///
+/// This allows output to be forwarded to a transport of your own, such as a UART driver or
+/// an in-memory buffer.
+///
+/// ```rust
+/// # use libc_print::{libc_println, set_output_hook, Stream};
+/// fn discard(_bytes: &[u8], _stream: Stream) {}
+///
+/// let previous = set_output_hook(Some(discard));
+/// libc_println!("this goes nowhere");
+/// set_output_hook(previous);
+/// ```
+///
+/// The hook is called with the bytes of each write, which is typically a whole line, and the
+/// stream the macro wrote to, before any redirection with [`set_stdout_fd`] or
+/// [`set_stderr_fd`]. Writes are reported as successful once the hook returns. The hook
+/// must not print using this crate itself. Raw writes made with [`libc_raw_panic_write`], and
+/// the output of the `_timeout` macros, which wait on the descriptor itself, are never passed
+/// to the hook.
+///
+/// [`libc_raw_panic_write`]: crate::libc_raw_panic_write
+pub fn set_output_hook(hook: Option<OutputHook>) -> Option<OutputHook> {
+    let hook = hook.map_or(core::ptr::null_mut(), |hook| hook as *mut ());
+    from_ptr(OUTPUT_HOOK.swap(hook, Ordering::AcqRel))
+}
+
+/// Passes `bytes` written to `fd` to the installed output hook, returning false if there
+/// isn't one.
+#[inline]
+pub(crate) fn write_hook(fd: i32, bytes: &[u8]) -> bool {
+    match from_ptr(OUTPUT_HOOK.load(Ordering::Acquire)) {
+        Some(hook) => {
+            hook(bytes, Stream::from_fd(fd));
+            true
+        }
+        None => false,
+    }
+}
+
+fn from_ptr(ptr: *mut ()) -> Option<OutputHook> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: the only non-null pointers stored are `OutputHook`s
+    Some(unsafe { core::mem::transmute::<*mut (), OutputHook>(ptr) })
+}
+
+/// Maps the standard handles to the descriptors they're currently redirected to.
+#[inline]
+pub(crate) fn resolve(fd: i32) -> i32 {
+    match fd {
+        crate::__LIBC_STDOUT => STDOUT_FD.load(Ordering::Relaxed),
+        crate::__LIBC_STDERR => STDERR_FD.load(Ordering::Relaxed),
+        fd => fd,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_stdout() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe {
+            libc::fcntl(fds[0], libc::F_SETFL, libc::O_NONBLOCK);
+        }
+
+        let previous = set_stdout_fd(fds[1]);
+        crate::libc_println!("captured {}", 42);
+        assert_eq!(set_stdout_fd(previous), fds[1]);
+        assert_eq!(resolve(crate::__LIBC_STDOUT), crate::__LIBC_STDOUT);
+
+        // Other tests may have printed while we were redirected, but our line is whole
+        let mut buf = [0_u8; 4096];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        assert!(n > 0);
+        let out = core::str::from_utf8(&buf[..n as usize]).unwrap();
+        assert!(out.lines().any(|line| line == "captured 42"), "{:?}", out);
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[test]
+    fn test_output_hook() {
+        use std::sync::Mutex;
+        use std::vec::Vec;
+
+        // Never opened, so anything written to it can only have come through the hook
+        const HOOKED_FD: i32 = 1000;
+        static HOOKED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+        // Other tests run concurrently, so pass their output through untouched
+        fn hook(bytes: &[u8], stream: Stream) {
+            let fd = match stream {
+                Stream::Stdout => crate::__LIBC_STDOUT,
+                Stream::Stderr => crate::__LIBC_STDERR,
+                Stream::Fd(fd) => fd,
+            };
+            if fd == HOOKED_FD {
+                HOOKED.lock().unwrap().extend_from_slice(bytes);
+            } else {
+                let _ = crate::write_fd(fd, bytes, crate::write_with::<crate::LibCBackend>);
+            }
+        }
+
+        assert!(set_output_hook(Some(hook)).is_none());
+        crate::libc_fprintln!(HOOKED_FD, "hooked {}", 42);
+        assert!(set_output_hook(None).is_some());
+        assert!(set_output_hook(None).is_none());
+
+        let expected = std::format!("hooked 42{}", crate::__LIBC_NEWLINE);
+        assert_eq!(&*HOOKED.lock().unwrap(), expected.as_bytes());
+        assert_eq!(Stream::from_fd(1), Stream::Stdout);
+        assert_eq!(Stream::from_fd(2), Stream::Stderr);
+        assert_eq!(Stream::from_fd(7), Stream::Fd(7));
+    }
+}
@@ -0,0 +1,166 @@
+//! A copy of the most recent output in a static ring buffer, for crash handlers to dump.
+
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// The number of bytes of the most recent output kept by the `ring-buffer` feature.
+pub const RING_CAPACITY: usize = 16 * 1024;
+
+static RING: Ring<RING_CAPACITY> = Ring::new();
+
+/// Writes the most recent output to `fd`, returning the number of bytes written or the OS
+/// error code on failure.
+///
+/// With the `ring-buffer` feature, everything written by the macros and writers in this
+/// crate is also copied into a static ring buffer holding the last [`RING_CAPACITY`] bytes,
+/// whether it was written to a descriptor, redirected, or passed to the output hook. This
+/// lets a crash handler emit recent diagnostics even if they originally went to a descriptor
+/// that has since been closed:
+///
+/// ```rust
+/// # use libc_print::{dump_ring, libc_println};
+/// libc_println!("connecting to {}", "upstream");
+/// // ... later, in a crash handler:
+/// dump_ring(2).unwrap();
+/// ```
+///
+/// Once the buffer has wrapped around, the dump starts at the first whole line. Raw writes,
+/// such as those made by the `_sigsafe` macros, aren't kept.
+///
+/// This is async-signal-safe: the buffer is updated without locks, and the dump is written
+/// straight to `fd`, bypassing redirection, the output hook and the `locked` feature's
+/// locks. Lines printed while the dump is in progress may appear partially.
+pub fn dump_ring(fd: i32) -> Result<usize, crate::Errno> {
+    let mut written = 0;
+    RING.dump::<i32>(|chunk| {
+        written += crate::write_with::<crate::LibCBackend>(fd, chunk)?;
+        Ok(())
+    })
+    .map_err(crate::Errno::from_raw)?;
+    Ok(written)
+}
+
+/// Copies `bytes` into the ring buffer.
+#[inline]
+pub(crate) fn record(bytes: &[u8]) {
+    RING.record(bytes);
+}
+
+struct Ring<const N: usize> {
+    /// The total number of bytes ever recorded, which gives the next position to write.
+    head: AtomicUsize,
+    bytes: [AtomicU8; N],
+}
+
+impl<const N: usize> Ring<N> {
+    const fn new() -> Ring<N> {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU8 = AtomicU8::new(0);
+        Ring {
+            head: AtomicUsize::new(0),
+            bytes: [ZERO; N],
+        }
+    }
+
+    fn record(&self, bytes: &[u8]) {
+        // Only the tail of a write larger than the buffer would survive
+        let bytes = &bytes[bytes.len().saturating_sub(N)..];
+        // Reserving the space first lets concurrent writers fill in their own parts
+        let start = self.head.fetch_add(bytes.len(), Ordering::Relaxed);
+        for (i, &b) in bytes.iter().enumerate() {
+            self.bytes[(start + i) % N].store(b, Ordering::Relaxed);
+        }
+    }
+
+    /// Passes the contents of the buffer to `f`, oldest first, a chunk at a time.
+    fn dump<E>(&self, mut f: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        let head = self.head.load(Ordering::Relaxed);
+        let mut pos = head.saturating_sub(N);
+        if pos > 0 {
+            // The oldest line has been partially overwritten, so skip to the next one
+            while pos < head && self.bytes[pos % N].load(Ordering::Relaxed) != b'\n' {
+                pos += 1;
+            }
+            pos += 1;
+        }
+
+        let mut chunk = [0_u8; 256];
+        while pos < head {
+            let len = (head - pos).min(chunk.len());
+            for (i, b) in chunk[..len].iter_mut().enumerate() {
+                *b = self.bytes[(pos + i) % N].load(Ordering::Relaxed);
+            }
+            f(&chunk[..len])?;
+            pos += len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn contents<const N: usize>(ring: &Ring<N>) -> Vec<u8> {
+        let mut out = Vec::new();
+        ring.dump::<()>(|chunk| {
+            out.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_ring() {
+        let ring = Ring::<16>::new();
+        assert_eq!(contents(&ring), b"");
+        ring.record(b"one\ntwo\n");
+        assert_eq!(contents(&ring), b"one\ntwo\n");
+        // Once wrapped, the partly overwritten first line is dropped
+        ring.record(b"three\nfour\n");
+        assert_eq!(contents(&ring), b"two\nthree\nfour\n");
+        ring.record(b"a much longer line than fits\n");
+        assert_eq!(contents(&ring), b"");
+
+        let ring = Ring::<1024>::new();
+        let line = [b'x'; 99];
+        for _ in 0..30 {
+            ring.record(&line);
+            ring.record(b"\n");
+        }
+        assert_eq!(contents(&ring).len(), 1000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dump_ring() {
+        // Other tests print enough to push our line out of the ring
+        let output = crate::tests::run_in_child("ring::tests::test_dump_ring", || {
+            crate::libc_println!("kept in the ring");
+
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+            unsafe {
+                libc::fcntl(fds[0], libc::F_SETFL, libc::O_NONBLOCK);
+            }
+            let n = dump_ring(fds[1]).unwrap();
+            assert!(n > 0 && n <= RING_CAPACITY);
+
+            let mut buf = std::vec![0_u8; RING_CAPACITY];
+            let read = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+            assert_eq!(read as usize, n);
+            let out = std::string::String::from_utf8_lossy(&buf[..n]);
+            assert!(
+                out.lines().any(|line| line == "kept in the ring"),
+                "{:?}",
+                out
+            );
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+        });
+        crate::tests::assert_success(&output);
+    }
+}
@@ -0,0 +1,144 @@
+//! ARM semihosting support, for bare-metal Cortex-M targets without a C library.
+//!
+//! Output is handed to the attached debugger with `SYS_WRITE`, which the host prints to its
+//! own console. The standard output and error are opened on the host's `:tt` console the
+//! first time they're written to; any other file descriptor is passed through as a handle
+//! returned by the host's `SYS_OPEN`.
+//!
+//! A semihosting call halts the core if no debugger is attached, so this is only suitable
+//! for builds that always run under one (or under an emulator such as QEMU).
+
+use core::arch::asm;
+use core::convert::TryFrom;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicIsize, Ordering};
+
+#[cfg(not(target_arch = "arm"))]
+compile_error!("the `semihosting` feature is only supported on ARM targets");
+
+#[cfg(feature = "c-stdio")]
+compile_error!("the `c-stdio` feature needs the C library, so can't be used with `semihosting`");
+
+mod op {
+    pub const SYS_OPEN: usize = 0x01;
+    pub const SYS_WRITE: usize = 0x05;
+    pub const SYS_ISTTY: usize = 0x09;
+    pub const SYS_CLOCK: usize = 0x10;
+    pub const SYS_ERRNO: usize = 0x13;
+    pub const SYS_EXIT: usize = 0x18;
+}
+
+/// `SYS_OPEN` modes equivalent to `fopen`'s `"w"` and `"a"`.
+const MODE_W: usize = 4;
+const MODE_A: usize = 8;
+
+/// The `SYS_EXIT` reason reported by `abort`.
+const ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN: usize = 0x20023;
+
+const EBADF: i32 = 9;
+
+// Semihosting calls can't be interrupted, so these use values that no host errno takes.
+pub const EINTR: i32 = -1;
+pub const EAGAIN: i32 = -2;
+pub const EWOULDBLOCK: i32 = EAGAIN;
+
+const UNOPENED: isize = -1;
+
+static STDOUT: AtomicIsize = AtomicIsize::new(UNOPENED);
+static STDERR: AtomicIsize = AtomicIsize::new(UNOPENED);
+
+/// Makes the semihosting call `op`, with `arg` being either a value or a pointer to a block
+/// of arguments, depending on the call.
+///
+/// # Safety
+///
+/// `arg` must be valid for the call, and a debugger must be attached.
+unsafe fn call(op: usize, arg: usize) -> isize {
+    let res;
+    // SAFETY: the caller guarantees that the call's arguments are valid
+    unsafe {
+        asm!(
+            "bkpt #0xab",
+            inout("r0") op => res,
+            in("r1") arg,
+            options(nostack, preserves_flags),
+        );
+    }
+    res
+}
+
+/// Returns the host handle for `fd`, opening the host's console for the standard output and
+/// error the first time they're used.
+fn handle(fd: i32) -> isize {
+    let (cell, mode) = match fd {
+        crate::__LIBC_STDOUT => (&STDOUT, MODE_W),
+        crate::__LIBC_STDERR => (&STDERR, MODE_A),
+        _ => return fd as isize,
+    };
+    let handle = cell.load(Ordering::Relaxed);
+    if handle != UNOPENED {
+        return handle;
+    }
+
+    // If two threads race here the console is opened twice, which is harmless
+    let name = b":tt\0";
+    let args = [name.as_ptr() as usize, mode, name.len() - 1];
+    // SAFETY: `args` is a valid `SYS_OPEN` block, and `name` is NUL-terminated
+    let handle = unsafe { call(op::SYS_OPEN, args.as_ptr() as usize) };
+    cell.store(handle, Ordering::Relaxed);
+    handle
+}
+
+/// Returns the host's `errno` for the last failed call.
+fn errno() -> i32 {
+    // SAFETY: `SYS_ERRNO` takes no arguments
+    unsafe { call(op::SYS_ERRNO, 0) as i32 }
+}
+
+/// Returns the number of bytes written, or the host's error code on failure.
+pub(crate) unsafe fn libc_write(fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+    let handle = handle(fd);
+    if handle < 0 {
+        return Err(EBADF);
+    }
+    let args = [handle as usize, bytes.as_ptr() as usize, bytes.len()];
+    // SAFETY: `args` is a valid `SYS_WRITE` block, and `bytes` is valid for reads
+    let unwritten = unsafe { call(op::SYS_WRITE, args.as_ptr() as usize) };
+    // `SYS_WRITE` returns the number of bytes that were *not* written
+    match usize::try_from(unwritten) {
+        Ok(unwritten) if unwritten <= bytes.len() => Ok(bytes.len() - unwritten),
+        _ => Err(errno()),
+    }
+}
+
+pub unsafe fn isatty(fd: i32) -> i32 {
+    let args = [handle(fd) as usize];
+    // SAFETY: `args` is a valid `SYS_ISTTY` block
+    unsafe { call(op::SYS_ISTTY, args.as_ptr() as usize) as i32 }
+}
+
+/// There are no C library buffers to flush.
+pub unsafe fn fflush(_stream: *mut c_void) -> i32 {
+    0
+}
+
+pub unsafe fn abort() -> ! {
+    // SAFETY: on 32-bit ARM, `SYS_EXIT` takes the reason code itself
+    unsafe {
+        call(op::SYS_EXIT, ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN);
+    }
+    // The debugger may choose to resume us
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Returns the time since execution started, as seconds and nanoseconds.
+///
+/// The host only reports this in hundredths of a second.
+pub(crate) fn monotonic_now() -> (u64, u32) {
+    // SAFETY: `SYS_CLOCK` takes no arguments
+    let centis = unsafe { call(op::SYS_CLOCK, 0) };
+    let centis = u64::try_from(centis).unwrap_or(0);
+    (centis / 100, (centis % 100) as u32 * 10_000_000)
+}
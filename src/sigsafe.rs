@@ -0,0 +1,190 @@
+use core::fmt::Write;
+
+/// Macro for printing to the standard output, with a newline, from a signal handler.
+///
+/// The line is formatted into a stack buffer of 256 bytes and emitted with exactly one
+/// `write` call, without taking any locks. See [`SignalSafeWriter`] for details.
+///
+/// ```rust
+/// # use libc_print::libc_println_sigsafe;
+/// let signal = 11;
+/// libc_println_sigsafe!("caught signal {}", signal);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_println_sigsafe {
+    ($($arg:tt)*) => {
+        $crate::__libc_fprintln_sigsafe!($crate::__LIBC_STDOUT, $($arg)*)
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, from a signal handler.
+///
+/// See [`libc_println_sigsafe`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_eprintln_sigsafe {
+    ($($arg:tt)*) => {
+        $crate::__libc_fprintln_sigsafe!($crate::__LIBC_STDERR, $($arg)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __libc_fprintln_sigsafe {
+    ($fd:expr $(,)?) => {
+        $crate::__libc_fprintln_sigsafe!($fd, "")
+    };
+    ($fd:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::SignalSafeWriter::<256>::new($fd);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.flush_line();
+            }
+        }
+    };
+}
+
+/// A writer that can be used from a signal handler: it formats into a fixed-size stack
+/// buffer of `N` bytes, and emits the whole buffer with exactly one `write` call.
+///
+/// Unlike the other writers in this crate, it never takes a lock (even with the `locked`
+/// feature), never retries, and ignores [`set_stdout_fd`](crate::set_stdout_fd) and the
+/// output hook, all of which could deadlock or misbehave if the signal interrupted a write
+/// in progress. Output that doesn't fit in the buffer is dropped, rather than written in a
+/// second call.
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use libc_print::SignalSafeWriter;
+///
+/// let addr = 0xdead_usize;
+/// let mut w = SignalSafeWriter::<128>::new(2);
+/// write!(w, "segfault at {:#x}", addr).unwrap();
+/// w.flush_line();
+/// ```
+///
+/// Formatting is async-signal-safe as long as the `Display` and `Debug` implementations
+/// involved are, which is the case for strings and the primitive types.
+pub struct SignalSafeWriter<const N: usize> {
+    fd: i32,
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> SignalSafeWriter<N> {
+    /// Creates a writer for the given file descriptor, with an empty buffer.
+    #[inline]
+    pub const fn new(fd: i32) -> SignalSafeWriter<N> {
+        SignalSafeWriter {
+            fd,
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Formats `args` into the buffer, truncating it if it doesn't fit.
+    #[inline]
+    pub fn write_fmt(&mut self, args: core::fmt::Arguments) -> core::fmt::Result {
+        Write::write_fmt(self, args)
+    }
+
+    /// Returns what has been written so far.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns true if anything has been dropped because the buffer was full.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Writes out the buffer with a single `write` call, and empties it.
+    pub fn flush(&mut self) {
+        crate::__libc_raw_write(self.fd, &self.buf[..self.len]);
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    /// Ends the buffer with a newline and writes it out, like [`flush`](Self::flush). If the
+    /// buffer is full, the newline replaces the end of the output.
+    pub fn flush_line(&mut self) {
+        let nl = crate::__LIBC_NEWLINE.as_bytes();
+        if nl.len() > N {
+            return self.flush();
+        }
+        if N - self.len < nl.len() {
+            self.len = N - nl.len();
+            self.truncated = true;
+        }
+        self.buf[self.len..self.len + nl.len()].copy_from_slice(nl);
+        self.len += nl.len();
+        self.flush();
+    }
+}
+
+impl<const N: usize> Write for SignalSafeWriter<N> {
+    /// Appends as much of `s` as fits, ending on a character boundary. Never fails.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut n = s.len().min(N - self.len);
+        if n < s.len() {
+            self.truncated = true;
+            while !s.is_char_boundary(n) {
+                n -= 1;
+            }
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncation() {
+        let mut w = SignalSafeWriter::<8>::new(1);
+        write!(w, "{}", 1234).unwrap();
+        assert_eq!((w.as_bytes(), w.is_truncated()), (&b"1234"[..], false));
+        // Multi-byte characters aren't split
+        write!(w, "abcé").unwrap();
+        assert_eq!((w.as_bytes(), w.is_truncated()), (&b"1234abc"[..], true));
+        w.len = 0;
+
+        #[cfg(unix)]
+        {
+            let mut fds = [0; 2];
+            assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+            let mut w = SignalSafeWriter::<8>::new(fds[1]);
+            write!(w, "overflowing").unwrap();
+            w.flush_line();
+            assert!(!w.is_truncated() && w.as_bytes().is_empty());
+
+            let mut buf = [0_u8; 16];
+            let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+            let nl = crate::__LIBC_NEWLINE;
+            assert_eq!(
+                core::str::from_utf8(&buf[..n as usize]).unwrap(),
+                std::format!("{}{}", &"overflowing"[..8 - nl.len()], nl)
+            );
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+        }
+
+        libc_println_sigsafe!("sigsafe {}", "stdout");
+        libc_eprintln_sigsafe!();
+    }
+}
@@ -0,0 +1,170 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static SYNC_WITH_STDIO: AtomicBool = AtomicBool::new(false);
+
+/// Flushes every buffered C `stdio` output stream (including `stdout` and `stderr`).
+///
+/// This crate writes directly to the underlying file descriptors, bypassing the C library's
+/// `FILE*` buffers. If linked C code also prints via `printf` and friends, call this before
+/// printing to make sure that anything C has buffered is emitted first.
+///
+/// Note that `fflush` takes the C library's stream locks, so this is not async-signal-safe.
+///
+/// On WASI, on Linux with the `no-libc` feature, or with the `semihosting` feature, where this
+/// crate doesn't use a C library, this does nothing.
+pub fn sync_with_stdio() {
+    // SAFETY: `fflush(NULL)` is defined to flush all open output streams.
+    #[cfg(not(target_os = "wasi"))]
+    unsafe {
+        crate::sys::fflush(core::ptr::null_mut());
+    }
+}
+
+/// Enables or disables calling [`sync_with_stdio`] automatically before every write this
+/// crate performs. Disabled by default.
+///
+/// This keeps output ordered relative to C `stdio` output at the cost of an extra library call
+/// per write.
+pub fn set_sync_with_stdio(enabled: bool) {
+    SYNC_WITH_STDIO.store(enabled, Ordering::Relaxed);
+}
+
+/// Called on the write path before bytes are handed to the OS.
+#[inline]
+pub(crate) fn before_write() {
+    if SYNC_WITH_STDIO.load(Ordering::Relaxed) {
+        sync_with_stdio();
+    }
+}
+
+/// Macro for flushing the C library's `stdout` and `stderr` streams.
+///
+/// With the `c-stdio` feature, output to the standard output and error is buffered by the C
+/// library, so this makes sure that it has been written. Otherwise, this flushes anything C
+/// code has buffered, like [`sync_with_stdio`].
+///
+/// ```rust
+/// # use libc_print::{libc_flush, libc_print};
+/// libc_print!("no newline yet");
+/// libc_flush!();
+/// ```
+#[macro_export]
+macro_rules! libc_flush {
+    () => {
+        $crate::sync_with_stdio()
+    };
+}
+
+#[cfg(all(feature = "c-stdio", feature = "no-libc", any(target_os = "linux", windows)))]
+compile_error!("the `c-stdio` feature needs the C library, so can't be used with `no-libc`");
+
+#[cfg(all(
+    feature = "c-stdio",
+    any(target_os = "linux", target_os = "android", target_os = "emscripten")
+))]
+extern "C" {
+    #[link_name = "stdout"]
+    static C_STDOUT: *mut libc::FILE;
+    #[link_name = "stderr"]
+    static C_STDERR: *mut libc::FILE;
+}
+
+#[cfg(all(
+    feature = "c-stdio",
+    any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    )
+))]
+extern "C" {
+    #[link_name = "__stdoutp"]
+    static C_STDOUT: *mut libc::FILE;
+    #[link_name = "__stderrp"]
+    static C_STDERR: *mut libc::FILE;
+}
+
+/// Returns the C library's `FILE` for `fd`, if `fd` is the standard output or error and the
+/// `c-stdio` feature is enabled on a supported platform.
+#[cfg(feature = "c-stdio")]
+#[inline]
+fn stream(fd: i32) -> Option<*mut libc::FILE> {
+    #[cfg(all(
+        feature = "c-stdio",
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "emscripten",
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+            target_os = "dragonfly"
+        )
+    ))]
+    // SAFETY: the C library initializes these before any Rust code runs
+    unsafe {
+        match fd {
+            crate::__LIBC_STDOUT => return Some(C_STDOUT),
+            crate::__LIBC_STDERR => return Some(C_STDERR),
+            _ => {}
+        }
+    }
+
+    #[cfg(all(feature = "c-stdio", windows, target_env = "msvc"))]
+    {
+        extern "C" {
+            fn __acrt_iob_func(index: u32) -> *mut libc::FILE;
+        }
+        match fd {
+            // SAFETY: indexes 1 and 2 are the CRT's `stdout` and `stderr`
+            crate::__LIBC_STDOUT | crate::__LIBC_STDERR => {
+                return Some(unsafe { __acrt_iob_func(fd as u32) })
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Writes `bytes` through the C library's buffered stream for `fd`, returning `None` if the
+/// caller should write to the descriptor itself.
+#[cfg(feature = "c-stdio")]
+#[inline]
+pub(crate) fn write_stream(fd: i32, bytes: &[u8]) -> Option<Result<usize, i32>> {
+    let stream = stream(fd)?;
+    // SAFETY: `bytes` is valid for reads of `bytes.len()` bytes, and `stream` is a valid `FILE`
+    let written = unsafe { libc::fwrite(bytes.as_ptr().cast(), 1, bytes.len(), stream) };
+    if written < bytes.len() {
+        return Some(Err(crate::errno()));
+    }
+    Some(Ok(written))
+}
+
+#[cfg(not(feature = "c-stdio"))]
+#[inline]
+pub(crate) fn write_stream(_fd: i32, _bytes: &[u8]) -> Option<Result<usize, i32>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_with_stdio() {
+        sync_with_stdio();
+        set_sync_with_stdio(true);
+        crate::libc_println!("synced with stdio");
+        set_sync_with_stdio(false);
+    }
+
+    #[test]
+    fn test_write_stream() {
+        assert_eq!(write_stream(7, b"not a standard stream"), None);
+        #[cfg(all(feature = "c-stdio", unix))]
+        assert_eq!(write_stream(crate::__LIBC_STDOUT, b"via stdio\n"), Some(Ok(10)));
+        crate::libc_flush!();
+    }
+}
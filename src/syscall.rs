@@ -0,0 +1,261 @@
+//! Direct Linux system calls, used in place of the C library with the `no-libc` feature.
+//!
+//! This mirrors the small part of the `libc` crate's API that the crate uses outside of its
+//! tests, so that the rest of the code can be written against either. The one difference is
+//! that there is no `errno`: calls return the negated error code instead.
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+compile_error!("the `no-libc` feature is only supported on x86_64 and aarch64 Linux");
+
+use core::arch::asm;
+use core::convert::TryFrom;
+use core::ffi::{c_char, c_void};
+
+pub const EINTR: i32 = 4;
+pub const EAGAIN: i32 = 11;
+pub const EWOULDBLOCK: i32 = EAGAIN;
+
+pub const POLLOUT: i16 = 0x4;
+pub const CLOCK_MONOTONIC: i32 = 1;
+
+pub const O_WRONLY: i32 = 0o1;
+pub const O_CREAT: i32 = 0o100;
+pub const O_TRUNC: i32 = 0o1000;
+pub const O_APPEND: i32 = 0o2000;
+pub const O_CLOEXEC: i32 = 0o2000000;
+
+const AT_FDCWD: i32 = -100;
+
+const SIGABRT: usize = 6;
+const TCGETS: usize = 0x5401;
+
+#[repr(C)]
+pub struct pollfd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+#[repr(C)]
+pub struct timespec {
+    pub tv_sec: i64,
+    pub tv_nsec: i64,
+}
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const READ: usize = 0;
+    pub const WRITE: usize = 1;
+    pub const CLOSE: usize = 3;
+    pub const IOCTL: usize = 16;
+    pub const GETPID: usize = 39;
+    pub const GETTID: usize = 186;
+    pub const CLOCK_GETTIME: usize = 228;
+    pub const EXIT_GROUP: usize = 231;
+    pub const TGKILL: usize = 234;
+    pub const OPENAT: usize = 257;
+    pub const PPOLL: usize = 271;
+}
+
+#[cfg(target_arch = "aarch64")]
+mod nr {
+    pub const IOCTL: usize = 29;
+    pub const OPENAT: usize = 56;
+    pub const CLOSE: usize = 57;
+    pub const PPOLL: usize = 73;
+    pub const READ: usize = 63;
+    pub const WRITE: usize = 64;
+    pub const EXIT_GROUP: usize = 94;
+    pub const CLOCK_GETTIME: usize = 113;
+    pub const TGKILL: usize = 131;
+    pub const GETPID: usize = 172;
+    pub const GETTID: usize = 178;
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn syscall(nr: usize, args: [usize; 5]) -> isize {
+    let ret;
+    // SAFETY: the caller guarantees that the arguments are valid for the call
+    unsafe {
+        asm!(
+            "syscall",
+            inlateout("rax") nr as isize => ret,
+            in("rdi") args[0],
+            in("rsi") args[1],
+            in("rdx") args[2],
+            in("r10") args[3],
+            in("r8") args[4],
+            lateout("rcx") _,
+            lateout("r11") _,
+            options(nostack),
+        );
+    }
+    ret
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn syscall(nr: usize, args: [usize; 5]) -> isize {
+    let ret;
+    // SAFETY: the caller guarantees that the arguments are valid for the call
+    unsafe {
+        asm!(
+            "svc 0",
+            in("x8") nr,
+            inlateout("x0") args[0] as isize => ret,
+            in("x1") args[1],
+            in("x2") args[2],
+            in("x3") args[3],
+            in("x4") args[4],
+            options(nostack),
+        );
+    }
+    ret
+}
+
+/// Returns the number of bytes written, or the negated error code on failure.
+pub unsafe fn write(fd: i32, buf: *const c_void, count: usize) -> isize {
+    // SAFETY: the caller guarantees that `buf` is valid for reads of `count` bytes
+    unsafe { syscall(nr::WRITE, [fd as usize, buf as usize, count, 0, 0]) }
+}
+
+/// Returns the number of bytes read, or the negated error code on failure.
+pub unsafe fn read(fd: i32, buf: *mut c_void, count: usize) -> isize {
+    // SAFETY: the caller guarantees that `buf` is valid for writes of `count` bytes
+    unsafe { syscall(nr::READ, [fd as usize, buf as usize, count, 0, 0]) }
+}
+
+/// Returns the new file descriptor, or the negated error code on failure.
+pub unsafe fn open(path: *const c_char, flags: i32, mode: u32) -> i32 {
+    // SAFETY: the caller guarantees that `path` is NUL-terminated
+    unsafe {
+        syscall(
+            nr::OPENAT,
+            [
+                AT_FDCWD as usize,
+                path as usize,
+                flags as usize,
+                mode as usize,
+                0,
+            ],
+        ) as i32
+    }
+}
+
+pub unsafe fn close(fd: i32) -> i32 {
+    // SAFETY: this call takes no pointers
+    unsafe { syscall(nr::CLOSE, [fd as usize, 0, 0, 0, 0]) as i32 }
+}
+
+pub unsafe fn isatty(fd: i32) -> i32 {
+    // Large enough for the kernel's `struct termios` on every supported architecture
+    let mut termios = [0_u32; 16];
+    // SAFETY: `TCGETS` writes at most a `struct termios` to `termios`
+    let res = unsafe {
+        syscall(
+            nr::IOCTL,
+            [fd as usize, TCGETS, termios.as_mut_ptr() as usize, 0, 0],
+        )
+    };
+    (res == 0) as i32
+}
+
+pub unsafe fn poll(fds: *mut pollfd, nfds: u64, timeout_ms: i32) -> i32 {
+    let timeout = timespec {
+        tv_sec: i64::from(timeout_ms / 1000),
+        tv_nsec: i64::from(timeout_ms % 1000) * 1_000_000,
+    };
+    // A negative timeout waits forever
+    let timeout_ptr = if timeout_ms < 0 {
+        core::ptr::null()
+    } else {
+        &timeout as *const timespec
+    };
+    // SAFETY: the caller guarantees that `fds` is valid for `nfds` entries
+    unsafe {
+        syscall(
+            nr::PPOLL,
+            [fds as usize, nfds as usize, timeout_ptr as usize, 0, 0],
+        ) as i32
+    }
+}
+
+pub unsafe fn clock_gettime(clock: i32, ts: *mut timespec) -> i32 {
+    // SAFETY: the caller guarantees that `ts` is valid for writes
+    unsafe { syscall(nr::CLOCK_GETTIME, [clock as usize, ts as usize, 0, 0, 0]) as i32 }
+}
+
+/// There are no C library buffers to flush.
+pub unsafe fn fflush(_stream: *mut c_void) -> i32 {
+    0
+}
+
+pub unsafe fn getpid() -> i32 {
+    // SAFETY: this call takes no pointers
+    unsafe { syscall(nr::GETPID, [0; 5]) as i32 }
+}
+
+pub unsafe fn gettid() -> i32 {
+    // SAFETY: this call takes no pointers
+    unsafe { syscall(nr::GETTID, [0; 5]) as i32 }
+}
+
+pub unsafe fn abort() -> ! {
+    // SAFETY: these calls take no pointers
+    unsafe {
+        let (pid, tid) = (getpid() as usize, gettid() as usize);
+        syscall(nr::TGKILL, [pid, tid, SIGABRT, 0, 0]);
+        // SIGABRT is being caught or ignored, so exit with the status it would have caused
+        loop {
+            syscall(nr::EXIT_GROUP, [128 + SIGABRT, 0, 0, 0, 0]);
+        }
+    }
+}
+
+/// Returns the number of bytes written, or the OS error code on failure.
+pub(crate) unsafe fn libc_write(handle: i32, bytes: &[u8]) -> Result<usize, i32> {
+    // SAFETY: `bytes` is valid for reads of `bytes.len()` bytes
+    let res = unsafe { write(handle, bytes.as_ptr().cast(), bytes.len()) };
+    usize::try_from(res).map_err(|_| -res as i32)
+}
+
+/// Returns the number of bytes read, or the OS error code on failure.
+pub(crate) unsafe fn libc_read(handle: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+    let res = unsafe { read(handle, buf.as_mut_ptr().cast(), buf.len()) };
+    usize::try_from(res).map_err(|_| -res as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syscalls() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { ::libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        assert_eq!(unsafe { libc_write(fds[1], b"direct") }, Ok(6));
+        assert_eq!(unsafe { libc_write(-1, b"bad") }, Err(::libc::EBADF));
+        assert_eq!(unsafe { isatty(fds[1]) }, 0);
+
+        let mut fd = pollfd {
+            fd: fds[1],
+            events: POLLOUT,
+            revents: 0,
+        };
+        assert_eq!(unsafe { poll(&mut fd, 1, 0) }, 1);
+        assert_eq!(fd.revents & POLLOUT, POLLOUT);
+
+        let mut ts = timespec {
+            tv_sec: -1,
+            tv_nsec: -1,
+        };
+        assert_eq!(unsafe { clock_gettime(CLOCK_MONOTONIC, &mut ts) }, 0);
+        assert!(ts.tv_sec >= 0 && (0..1_000_000_000).contains(&ts.tv_nsec));
+
+        unsafe {
+            ::libc::close(fds[0]);
+            ::libc::close(fds[1]);
+        }
+    }
+}
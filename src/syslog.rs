@@ -0,0 +1,102 @@
+//! `syslog(3)` support, for daemons whose standard output and error aren't captured.
+
+use core::ffi::CStr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(all(feature = "no-libc", target_os = "linux"))]
+compile_error!("the `syslog` feature needs the C library, so can't be used with `no-libc`");
+
+static SYSLOG: AtomicBool = AtomicBool::new(false);
+
+/// The syslog facility that messages are logged under, set with [`open_syslog`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facility {
+    User,
+    Daemon,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl Facility {
+    fn code(self) -> libc::c_int {
+        match self {
+            Facility::User => libc::LOG_USER,
+            Facility::Daemon => libc::LOG_DAEMON,
+            Facility::Local0 => libc::LOG_LOCAL0,
+            Facility::Local1 => libc::LOG_LOCAL1,
+            Facility::Local2 => libc::LOG_LOCAL2,
+            Facility::Local3 => libc::LOG_LOCAL3,
+            Facility::Local4 => libc::LOG_LOCAL4,
+            Facility::Local5 => libc::LOG_LOCAL5,
+            Facility::Local6 => libc::LOG_LOCAL6,
+            Facility::Local7 => libc::LOG_LOCAL7,
+        }
+    }
+}
+
+/// Opens the system log with the identity `ident`, and sends everything written to the
+/// standard output and error there instead: the standard output at `LOG_INFO` severity, and
+/// the standard error at `LOG_ERR`.
+///
+/// ```rust,no_run
+/// # use libc_print::{libc_eprintln, open_syslog, Facility};
+/// use core::ffi::CStr;
+///
+/// open_syslog(CStr::from_bytes_with_nul(b"mydaemon\0").unwrap(), Facility::Daemon);
+/// libc_eprintln!("can't reach {}", "upstream");
+/// //  ^-- logs: mydaemon[1234]: can't reach upstream
+/// ```
+///
+/// Each write becomes a message of its own, so lines should be printed whole, as the
+/// `println` macros do. The setting is process-wide.
+pub fn open_syslog(ident: &'static CStr, facility: Facility) {
+    // SAFETY: `ident` lives forever, as `syslog` keeps using it after `openlog` returns
+    unsafe { libc::openlog(ident.as_ptr(), libc::LOG_PID, facility.code()) };
+    SYSLOG.store(true, Ordering::Relaxed);
+}
+
+/// Closes the system log opened by [`open_syslog`], so output goes back to the standard
+/// output and error.
+pub fn close_syslog() {
+    SYSLOG.store(false, Ordering::Relaxed);
+    // SAFETY: `closelog` has no preconditions
+    unsafe { libc::closelog() };
+}
+
+/// Sends `bytes` to the system log if it's open and `fd` is the standard output or error,
+/// returning `None` if the caller should write to the descriptor itself.
+pub(crate) fn write_log(fd: i32, bytes: &[u8]) -> Option<Result<usize, i32>> {
+    if !SYSLOG.load(Ordering::Relaxed) {
+        return None;
+    }
+    let severity = match fd {
+        crate::__LIBC_STDOUT => libc::LOG_INFO,
+        crate::__LIBC_STDERR => libc::LOG_ERR,
+        _ => return None,
+    };
+
+    let _ = crate::logline::for_each_message::<()>(bytes, |msg| {
+        // SAFETY: the format takes one string argument, and `msg` is NUL-terminated
+        unsafe { libc::syslog(severity, b"%s\0".as_ptr().cast(), msg) };
+        Ok(())
+    });
+    Some(Ok(bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closed_syslog() {
+        // Nothing is taken over until the log is opened
+        assert_eq!(write_log(crate::__LIBC_STDERR, b"not logged\n"), None);
+        assert_eq!(Facility::Daemon.code(), libc::LOG_DAEMON);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{__LibCBufWriter, Backend, LibCBackend, __LIBC_BUFFER_SIZE};
+use core::cell::Cell;
+use core::fmt::Write;
+
+/// Writes are split into chunks no larger than POSIX's minimum `PIPE_BUF`, so that a `poll`
+/// reporting the descriptor as writable means the following write can't block for long.
+const TIMEOUT_CHUNK: usize = 512;
+
+/// Macro for printing to the standard output, with a newline, waiting at most `ms`
+/// milliseconds for the output to become writable.
+///
+/// If the output would block for longer than the timeout (for example, a pipe whose reader
+/// is not keeping up), the remainder of the line is dropped instead. With the `c-stdio`
+/// feature, the line is handed to the C library's stream without waiting.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+#[macro_export]
+macro_rules! libc_println_timeout {
+    ($ms:expr) => { $crate::libc_println_timeout!($ms, "") };
+    ($ms:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCTimeoutWriter::new($crate::__LIBC_STDOUT, $ms);
+                $crate::__libc_line_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, waiting at most `ms`
+/// milliseconds for the output to become writable.
+///
+/// If the output would block for longer than the timeout, the remainder of the line is
+/// dropped instead. With the `c-stdio` feature, the line is handed to the C library's stream
+/// without waiting.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[cfg(not(any(windows, target_os = "wasi", feature = "semihosting")))]
+#[macro_export]
+macro_rules! libc_eprintln_timeout {
+    ($ms:expr) => { $crate::libc_eprintln_timeout!($ms, "") };
+    ($ms:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCTimeoutWriter::new($crate::__LIBC_STDERR, $ms);
+                $crate::__libc_line_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// A writer that collects a line like [`__LibCBufWriter`], but waits a bounded amount of time
+/// before each write, and drops the rest of its output once a wait times out.
+///
+/// [`__LibCBufWriter`]: crate::__LibCBufWriter
+#[doc(hidden)]
+pub struct __LibCTimeoutWriter<const N: usize = __LIBC_BUFFER_SIZE> {
+    inner: __LibCBufWriter<N>,
+}
+
+impl<const N: usize> Write for __LibCTimeoutWriter<N> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.inner.write_str(s)
+    }
+}
+
+impl __LibCTimeoutWriter {
+    #[inline]
+    pub fn new(handle: i32, timeout_ms: i32) -> __LibCTimeoutWriter {
+        __LibCTimeoutWriter {
+            inner: __LibCBufWriter::with_timeout(handle, timeout_ms),
+        }
+    }
+}
+
+impl<const N: usize> __LibCTimeoutWriter<N> {
+    #[inline]
+    pub fn write_fmt(&mut self, args: core::fmt::Arguments) -> core::fmt::Result {
+        self.inner.write_fmt(args)
+    }
+
+    #[inline]
+    pub fn write_nl(&mut self) -> core::fmt::Result {
+        self.inner.write_nl()
+    }
+
+    /// Writes out anything that has been buffered so far.
+    #[inline]
+    pub fn flush(&mut self) -> core::fmt::Result {
+        self.inner.flush()
+    }
+
+    /// Returns true if any of the writes so far were dropped because of the timeout.
+    #[inline]
+    pub fn timed_out(&self) -> bool {
+        self.inner.timed_out()
+    }
+}
+
+/// How long to wait before each write, and whether a wait has timed out.
+pub(crate) struct Timeout {
+    ms: i32,
+    timed_out: Cell<bool>,
+}
+
+impl Timeout {
+    pub(crate) fn new(ms: i32) -> Timeout {
+        Timeout {
+            ms,
+            timed_out: Cell::new(false),
+        }
+    }
+
+    pub(crate) fn timed_out(&self) -> bool {
+        self.timed_out.get()
+    }
+
+    /// Writes `msg` to `handle` like any other output, except that the final write to the
+    /// descriptor waits up to the timeout before each chunk. Once a wait times out, drops this
+    /// and any later output.
+    pub(crate) fn write(&self, handle: i32, msg: &[u8]) -> core::fmt::Result {
+        if self.timed_out() {
+            return Err(core::fmt::Error);
+        }
+        match crate::write_all_with(handle, msg, |fd, msg| self.write_fd(fd, msg)) {
+            Ok(_) => Ok(()),
+            // Dropping the line is expected, so isn't reported as a failure
+            Err(_) if self.timed_out() => Err(core::fmt::Error),
+            Err(_) => {
+                crate::panic::write_failed(handle);
+                Err(core::fmt::Error)
+            }
+        }
+    }
+
+    fn write_fd(&self, fd: i32, msg: &[u8]) -> Result<usize, i32> {
+        let mut written = 0;
+        while written < msg.len() {
+            if !poll_writable(fd, self.ms) {
+                self.timed_out.set(true);
+                return Err(crate::sys::EAGAIN);
+            }
+            let end = msg.len().min(written + TIMEOUT_CHUNK);
+            match LibCBackend::write(fd, &msg[written..end]) {
+                Ok(0) => return Err(0),
+                Ok(res) => written += res,
+                Err(crate::sys::EINTR) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Waits up to `timeout_ms` for `handle` to accept more output.
+pub(crate) fn poll_writable(handle: i32, timeout_ms: i32) -> bool {
+    let mut fd = crate::sys::pollfd {
+        fd: handle,
+        events: crate::sys::POLLOUT,
+        revents: 0,
+    };
+    // SAFETY: `fd` is a valid, exclusively borrowed pollfd for the duration of the call.
+    let res = unsafe { crate::sys::poll(&mut fd, 1, timeout_ms) };
+    res > 0 && fd.revents & crate::sys::POLLOUT != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipe() -> [i32; 2] {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        fds
+    }
+
+    #[test]
+    fn test_timeout_writable() {
+        let [rd, wr] = pipe();
+        let mut stm = __LibCTimeoutWriter::new(wr, 100);
+        write!(stm, "hello {}", 1).unwrap();
+        stm.flush().unwrap();
+        assert!(!stm.timed_out());
+
+        let mut buf = [0_u8; 16];
+        let n = unsafe { libc::read(rd, buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(&buf[..n as usize], b"hello 1");
+        unsafe {
+            libc::close(rd);
+            libc::close(wr);
+        }
+    }
+
+    #[test]
+    fn test_timeout_full_pipe() {
+        let [rd, wr] = pipe();
+
+        // Fill the pipe without blocking, then switch back to a blocking descriptor
+        unsafe {
+            let flags = libc::fcntl(wr, libc::F_GETFL);
+            libc::fcntl(wr, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            let chunk = [0_u8; TIMEOUT_CHUNK];
+            while libc::write(wr, chunk.as_ptr().cast(), chunk.len()) > 0 {}
+            libc::fcntl(wr, libc::F_SETFL, flags);
+        }
+
+        let mut stm = __LibCTimeoutWriter::new(wr, 10);
+        write!(stm, "dropped").unwrap();
+        stm.write_nl().unwrap();
+        assert!(stm.flush().is_err());
+        assert!(stm.timed_out());
+        unsafe {
+            libc::close(rd);
+            libc::close(wr);
+        }
+    }
+
+    #[test]
+    fn test_timeout_single_write() {
+        // Datagram sockets keep each write as a separate message
+        let mut fds = [0; 2];
+        assert_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+
+        let mut stm = __LibCTimeoutWriter::new(fds[1], 100);
+        write!(stm, "one {} line {}", 2, 3).unwrap();
+        stm.write_nl().unwrap();
+        drop(stm);
+
+        let mut buf = [0_u8; 64];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+        let expected = std::format!("one 2 line 3{}", crate::__LIBC_NEWLINE);
+        assert_eq!(&buf[..n as usize], expected.as_bytes());
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+
+    #[test]
+    fn test_timeout_output_hook() {
+        use std::{sync::Mutex, vec::Vec};
+
+        static HOOKED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+        fn hook(bytes: &[u8], _: crate::Stream) {
+            HOOKED.lock().unwrap().extend_from_slice(bytes);
+        }
+
+        // The hook would catch the output of tests running alongside
+        let output = crate::tests::run_in_child("timeout::tests::test_timeout_output_hook", || {
+            crate::set_output_hook(Some(hook));
+            libc_println_timeout!(100, "hooked {}", 1);
+            crate::set_output_hook(None);
+            let expected = std::format!("hooked 1{}", crate::__LIBC_NEWLINE);
+            assert_eq!(&*HOOKED.lock().unwrap(), expected.as_bytes());
+        });
+        crate::tests::assert_success(&output);
+    }
+
+    #[test]
+    fn test_println_timeout() {
+        libc_println_timeout!(100, "stdout with timeout = {}", 100);
+        libc_eprintln_timeout!(100);
+    }
+}
@@ -0,0 +1,185 @@
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// Room for the brackets, the decimal point, a trailing space, the 9 digits of nanoseconds
+/// and up to 20 digits of seconds.
+const TIMESTAMP_BUFFER: usize = 33;
+
+/// Macro for printing to the standard output, with a newline, prefixed with the time since
+/// an arbitrary fixed point as `[<secs>.<nanos>]`.
+///
+/// The time comes from the monotonic clock, so is unaffected by changes to the system time.
+///
+/// ```rust
+/// # use libc_print::libc_tprintln;
+/// libc_tprintln!("booted");
+/// //  ^-- prints: [12345.678901234] booted
+/// ```
+///
+/// The prefix and message are written together, so don't interleave with other output.
+/// Requires the `timestamp` feature.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[cfg(feature = "timestamp")]
+#[macro_export]
+macro_rules! libc_tprintln {
+    () => { $crate::libc_tprintln!("") };
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                $crate::__libc_tline_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error, with a newline, prefixed with the time since
+/// an arbitrary fixed point as `[<secs>.<nanos>]`.
+///
+/// See [`libc_tprintln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[cfg(feature = "timestamp")]
+#[macro_export]
+macro_rules! libc_teprintln {
+    () => { $crate::libc_teprintln!("") };
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_tline_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Enables or disables prefixing every line printed by [`libc_println`], [`libc_eprintln`],
+/// [`libc_fprintln`] and the other line-oriented macros with a timestamp, as [`libc_tprintln`]
+/// does. Disabled by default.
+///
+/// The timestamp comes before any tag set with [`set_line_prefix`], and is left off the same
+/// output as the tag is, such as [`libc_json`] lines and multi-line blocks like
+/// [`libc_hexdump`].
+///
+/// ```rust
+/// # use libc_print::{libc_println, set_timestamps};
+/// set_timestamps(true);
+/// libc_println!("shutting down");
+/// //  ^-- prints: [12345.678901234] shutting down
+/// ```
+///
+/// Requires the `timestamp` feature.
+///
+/// [`libc_println`]: crate::libc_println
+/// [`libc_eprintln`]: crate::libc_eprintln
+/// [`libc_fprintln`]: crate::libc_fprintln
+/// [`set_line_prefix`]: crate::set_line_prefix
+/// [`libc_json`]: crate::libc_json
+/// [`libc_hexdump`]: crate::libc_hexdump
+pub fn set_timestamps(enabled: bool) {
+    TIMESTAMPS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns true if every line should be prefixed with a timestamp.
+#[inline]
+pub(crate) fn enabled() -> bool {
+    TIMESTAMPS.load(Ordering::Relaxed)
+}
+
+/// Writes the current monotonic time to `w` as `[<secs>.<nanos>] `.
+#[doc(hidden)]
+pub fn __libc_timestamp<W: Write>(w: &mut W) -> core::fmt::Result {
+    let (secs, nanos) = crate::clock::monotonic_now();
+    let mut buf = [0_u8; TIMESTAMP_BUFFER];
+    w.write_str(format_timestamp(&mut buf, secs, nanos))
+}
+
+/// Formats a timestamp into the end of `buf`, returning the formatted part.
+fn format_timestamp(buf: &mut [u8; TIMESTAMP_BUFFER], mut secs: u64, mut nanos: u32) -> &str {
+    let mut pos = buf.len();
+    let mut push = |b: u8| {
+        pos -= 1;
+        buf[pos] = b;
+    };
+
+    push(b' ');
+    push(b']');
+    for _ in 0..9 {
+        push(b'0' + (nanos % 10) as u8);
+        nanos /= 10;
+    }
+    push(b'.');
+    loop {
+        push(b'0' + (secs % 10) as u8);
+        secs /= 10;
+        if secs == 0 {
+            break;
+        }
+    }
+    push(b'[');
+
+    // SAFETY: we only wrote ASCII digits and punctuation
+    unsafe { core::str::from_utf8_unchecked(&buf[pos..]) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_format_timestamp() {
+        let mut buf = [0; TIMESTAMP_BUFFER];
+        assert_eq!(format_timestamp(&mut buf, 0, 0), "[0.000000000] ");
+        assert_eq!(format_timestamp(&mut buf, 12, 3_400), "[12.000003400] ");
+        assert_eq!(
+            format_timestamp(&mut buf, u64::MAX, 999_999_999),
+            "[18446744073709551615.999999999] "
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let mut s = String::new();
+        __libc_timestamp(&mut s).unwrap();
+        assert!(s.starts_with('[') && s.ends_with("] "), "{:?}", s);
+
+        libc_tprintln!("timestamped {}", 1);
+        libc_teprintln!();
+    }
+
+    #[test]
+    fn test_set_timestamps() {
+        // Other tests' output would be affected
+        let output = crate::tests::run_in_child("timestamp::tests::test_set_timestamps", || {
+            set_timestamps(true);
+            crate::libc_println!("stamped");
+            crate::libc_fastln!("fast");
+            libc_tprintln!("once");
+            set_timestamps(false);
+            crate::libc_println!("plain");
+        });
+        crate::tests::assert_success(&output);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines().skip_while(|line| !line.ends_with("] stamped"));
+        // The test harness may have started the line with the test's name
+        let (_, stamp) = lines.next().unwrap().rsplit_once('[').unwrap();
+        let (secs, nanos) = stamp.trim_end_matches("] stamped").split_once('.').unwrap();
+        assert!(secs.parse::<u64>().is_ok() && nanos.len() == 9, "{:?}", stdout);
+        let fast = lines.next().unwrap();
+        assert!(fast.starts_with('[') && fast.ends_with("] fast"), "{:?}", stdout);
+        // `libc_tprintln!` doesn't stamp its lines twice
+        let once = lines.next().unwrap();
+        assert!(once.ends_with("] once") && once.matches('[').count() == 1);
+        assert_eq!(lines.next(), Some("plain"));
+    }
+}
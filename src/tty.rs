@@ -0,0 +1,47 @@
+/// Returns true if `fd` refers to a terminal, so output written to it can be colored or laid
+/// out for people rather than for other programs.
+///
+/// ```rust
+/// # use libc_print::{is_terminal, libc_println};
+/// if is_terminal(1) {
+///     libc_println!("\x1b[1mready\x1b[0m");
+/// } else {
+///     libc_println!("ready");
+/// }
+/// ```
+///
+/// The standard output and error are checked after any redirection with
+/// [`set_stdout_fd`](crate::set_stdout_fd) and [`set_stderr_fd`](crate::set_stderr_fd), so
+/// this describes where this crate's output actually goes.
+///
+/// This uses `isatty` on Unix and `GetConsoleMode` on Windows, where only the standard input,
+/// output and error are recognised as consoles. Hermit never has a terminal, and with the
+/// `semihosting` feature the debugger's host decides.
+pub fn is_terminal(fd: i32) -> bool {
+    let fd = crate::redirect::resolve(fd);
+    // SAFETY: `isatty` accepts any descriptor value
+    unsafe { isatty(fd) == 1 }
+}
+
+#[cfg(not(windows))]
+use crate::sys::isatty;
+// The C runtime's `isatty` is true for any character device, including `NUL`
+#[cfg(windows)]
+use crate::windows::isatty;
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_terminal() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        assert!(!is_terminal(fds[0]) && !is_terminal(fds[1]));
+        assert!(!is_terminal(-1));
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! [`ufmt`] support, for formatting without the code size of `core::fmt`.
+//!
+//! The `ufmt` macros refer to the `ufmt` crate by name, so code using the macros here also
+//! needs to depend on `ufmt` directly.
+
+use ufmt::uWrite;
+
+/// Macro for printing to the standard output using [`ufmt`] rather than `core::fmt`.
+///
+/// Only types implementing `ufmt`'s `uDisplay` and `uDebug` traits can be formatted, but this
+/// avoids pulling the `core::fmt` machinery into the binary.
+///
+/// ```rust
+/// # use libc_print::libc_uwriteln;
+/// libc_uwriteln!("{} of {}", 3, 10);
+/// libc_uwriteln!("{:?}", Some(5));
+/// ```
+///
+/// Requires the `ufmt` feature, and a dependency on the `ufmt` crate.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_uwriteln {
+    () => { $crate::libc_uwriteln!("") };
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                $crate::__libc_line_prefix(&mut stm);
+                ufmt::uwrite!(stm, $($arg)*);
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard output using [`ufmt`] rather than `core::fmt`, without
+/// a newline.
+///
+/// See [`libc_uwriteln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_uwrite {
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT);
+                ufmt::uwrite!(stm, $($arg)*);
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error using [`ufmt`] rather than `core::fmt`.
+///
+/// See [`libc_uwriteln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_euwriteln {
+    () => { $crate::libc_euwriteln!("") };
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                $crate::__libc_line_prefix(&mut stm);
+                ufmt::uwrite!(stm, $($arg)*);
+                stm.write_nl();
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error using [`ufmt`] rather than `core::fmt`, without
+/// a newline.
+///
+/// See [`libc_uwriteln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_euwrite {
+    ($($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCBufWriter::new($crate::__LIBC_STDERR);
+                ufmt::uwrite!(stm, $($arg)*);
+            }
+        }
+    };
+}
+
+impl uWrite for crate::LibCWriter {
+    type Error = core::fmt::Error;
+
+    #[inline]
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        crate::LibCWriter::write_str(self, s)
+    }
+}
+
+impl<const N: usize> uWrite for crate::__LibCBufWriter<N> {
+    type Error = core::fmt::Error;
+
+    #[inline]
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        crate::__LibCBufWriter::write_str(self, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uwriteln() {
+        libc_uwriteln!("{} + {} = {}", 1, 2_u8, 3_i64);
+        libc_uwrite!("ufmt ");
+        libc_uwriteln!();
+        libc_euwriteln!("{:?}", -1);
+        libc_euwrite!("{}\n", "stderr");
+
+        let mut w = crate::LibCWriter::stdout();
+        ufmt::uwriteln!(w, "writer {}", 42).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+//! Windows console support.
+//!
+//! The CRT's `write` passes bytes through to the console unchanged, which mangles UTF-8 unless
+//! the console's code page happens to be UTF-8. When the standard handles are attached to a
+//! real console we transcode to UTF-16 ourselves and use `WriteConsoleW` instead.
+//!
+//! With the `no-libc` feature, this also stands in for the C runtime, writing to redirected
+//! standard handles with `WriteFile`. Only the standard output and error can be written to, as
+//! there are no other file descriptors without the C runtime.
+
+use core::convert::TryFrom;
+use core::ffi::c_void;
+
+type Handle = *mut c_void;
+
+const STD_INPUT_HANDLE: u32 = -10_i32 as u32;
+const STD_OUTPUT_HANDLE: u32 = -11_i32 as u32;
+const STD_ERROR_HANDLE: u32 = -12_i32 as u32;
+
+const ERROR_INVALID_HANDLE: i32 = 6;
+
+// `WriteFile` never fails like this, so these use values that no Windows error code takes.
+pub const EINTR: i32 = -1;
+pub const EAGAIN: i32 = -2;
+pub const EWOULDBLOCK: i32 = EAGAIN;
+
+/// The number of UTF-16 code units transcoded per `WriteConsoleW` call.
+const CONSOLE_CHUNK: usize = 256;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetStdHandle(std_handle: u32) -> Handle;
+    fn GetConsoleMode(console: Handle, mode: *mut u32) -> i32;
+    fn WriteConsoleW(
+        console: Handle,
+        buffer: *const c_void,
+        chars_to_write: u32,
+        chars_written: *mut u32,
+        reserved: *mut c_void,
+    ) -> i32;
+    fn WriteFile(
+        file: Handle,
+        buffer: *const c_void,
+        bytes_to_write: u32,
+        bytes_written: *mut u32,
+        overlapped: *mut c_void,
+    ) -> i32;
+    fn GetLastError() -> u32;
+    fn GetCurrentProcess() -> Handle;
+    fn GetCurrentProcessId() -> u32;
+    fn GetCurrentThreadId() -> u32;
+    fn QueryPerformanceCounter(count: *mut i64) -> i32;
+    fn QueryPerformanceFrequency(frequency: *mut i64) -> i32;
+    fn TerminateProcess(process: Handle, exit_code: u32) -> i32;
+}
+
+/// Returns the standard handle backing `fd`, if `fd` is the standard output or error.
+fn std_handle(fd: i32) -> Option<Handle> {
+    let std_handle = match fd {
+        crate::__LIBC_STDOUT => STD_OUTPUT_HANDLE,
+        crate::__LIBC_STDERR => STD_ERROR_HANDLE,
+        _ => return None,
+    };
+    // SAFETY: `GetStdHandle` accepts any value
+    let handle = unsafe { GetStdHandle(std_handle) };
+    if handle.is_null() || handle as isize == -1 {
+        return None;
+    }
+    Some(handle)
+}
+
+/// Returns the console handle backing `fd`, if `fd` is the standard output or error and is
+/// attached to a console rather than redirected.
+fn console_handle(fd: i32) -> Option<Handle> {
+    let handle = std_handle(fd)?;
+    let mut mode = 0;
+    // SAFETY: `GetConsoleMode` accepts any handle value and only writes to `mode`
+    if unsafe { GetConsoleMode(handle, &mut mode) } == 0 {
+        return None;
+    }
+    Some(handle)
+}
+
+/// Writes `bytes` to the console if `fd` refers to one, returning `None` if the caller should
+/// fall back to writing bytes.
+///
+/// Invalid UTF-8 is written as U+FFFD.
+pub(crate) fn write_console(fd: i32, bytes: &[u8]) -> Option<Result<usize, i32>> {
+    let handle = console_handle(fd)?;
+
+    let mut buf = [0_u16; CONSOLE_CHUNK];
+    let mut len = 0;
+    // Bytes of input transcoded into `buf`, and bytes already handed to the console
+    let mut pos = 0;
+    let mut flushed = 0;
+
+    while pos < bytes.len() {
+        if len + 2 > buf.len() {
+            if let Err(e) = write_units(handle, &buf[..len]) {
+                return Some(if flushed > 0 { Ok(flushed) } else { Err(e) });
+            }
+            flushed = pos;
+            len = 0;
+        }
+        let (c, n) = decode_char(&bytes[pos..]);
+        len += c.encode_utf16(&mut buf[len..]).len();
+        pos += n;
+    }
+
+    if let Err(e) = write_units(handle, &buf[..len]) {
+        return Some(if flushed > 0 { Ok(flushed) } else { Err(e) });
+    }
+
+    Some(Ok(bytes.len()))
+}
+
+/// Decodes the char at the start of `bytes`, returning it and its encoded length.
+pub(crate) fn decode_char(bytes: &[u8]) -> (char, usize) {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 0,
+    };
+    if len > 0 && len <= bytes.len() {
+        if let Some(c) = core::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+        {
+            return (c, len);
+        }
+    }
+    (char::REPLACEMENT_CHARACTER, 1)
+}
+
+fn write_units(handle: Handle, mut units: &[u16]) -> Result<(), i32> {
+    while !units.is_empty() {
+        let mut written = 0;
+        // SAFETY: `units` is valid for reads of `units.len()` UTF-16 code units
+        let ok = unsafe {
+            WriteConsoleW(
+                handle,
+                units.as_ptr().cast(),
+                units.len() as u32,
+                &mut written,
+                core::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(unsafe { GetLastError() } as i32);
+        }
+        if written == 0 {
+            return Err(0);
+        }
+        units = &units[(written as usize).min(units.len())..];
+    }
+    Ok(())
+}
+
+/// Returns the number of bytes written, or the Windows error code on failure.
+#[cfg(feature = "no-libc")]
+pub(crate) unsafe fn libc_write(fd: i32, bytes: &[u8]) -> Result<usize, i32> {
+    if let Some(res) = write_console(fd, bytes) {
+        return res;
+    }
+    let handle = std_handle(fd).ok_or(ERROR_INVALID_HANDLE)?;
+    let mut written = 0;
+    // SAFETY: `bytes` is valid for reads of at least the requested number of bytes
+    let ok = unsafe {
+        WriteFile(
+            handle,
+            bytes.as_ptr().cast(),
+            u32::try_from(bytes.len()).unwrap_or(u32::MAX),
+            &mut written,
+            core::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(unsafe { GetLastError() } as i32);
+    }
+    Ok(written as usize)
+}
+
+pub unsafe fn isatty(fd: i32) -> i32 {
+    if fd == crate::__LIBC_STDIN {
+        // SAFETY: `GetStdHandle` accepts any value
+        let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        let mut mode = 0;
+        // SAFETY: `GetConsoleMode` accepts any handle value and only writes to `mode`
+        return unsafe { GetConsoleMode(handle, &mut mode) != 0 } as i32;
+    }
+    console_handle(fd).is_some() as i32
+}
+
+#[cfg(feature = "no-libc")]
+pub unsafe fn getpid() -> i32 {
+    // SAFETY: `GetCurrentProcessId` has no preconditions
+    unsafe { GetCurrentProcessId() as i32 }
+}
+
+/// There are no C runtime buffers to flush.
+pub unsafe fn fflush(_stream: *mut c_void) -> i32 {
+    0
+}
+
+pub unsafe fn abort() -> ! {
+    // SAFETY: `GetCurrentProcess` returns a pseudo handle that is always valid
+    unsafe {
+        // Exit with the same status as the C runtime's `abort`
+        TerminateProcess(GetCurrentProcess(), 3);
+    }
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Returns the time since an arbitrary fixed point, from the performance counter, as seconds
+/// and nanoseconds.
+pub(crate) fn monotonic_now() -> (u64, u32) {
+    let mut count = 0;
+    let mut frequency = 0;
+    // SAFETY: both calls only write to the given integer, and can't fail since Windows XP
+    unsafe {
+        QueryPerformanceCounter(&mut count);
+        QueryPerformanceFrequency(&mut frequency);
+    }
+    let (count, frequency) = (count as u64, (frequency as u64).max(1));
+    let nanos = (count % frequency) * 1_000_000_000 / frequency;
+    (count / frequency, nanos as u32)
+}
+
+/// Returns the id of the calling thread.
+pub(crate) fn current_thread_id() -> u64 {
+    // SAFETY: `GetCurrentThreadId` has no preconditions
+    u64::from(unsafe { GetCurrentThreadId() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_char() {
+        assert_eq!(decode_char(b"a"), ('a', 1));
+        assert_eq!(decode_char("é".as_bytes()), ('é', 2));
+        assert_eq!(decode_char("日本".as_bytes()), ('日', 3));
+        assert_eq!(decode_char(&[0xff, b'a']), (char::REPLACEMENT_CHARACTER, 1));
+        // Truncated sequence
+        assert_eq!(decode_char(&[0xe6, 0x97]), (char::REPLACEMENT_CHARACTER, 1));
+    }
+
+    #[cfg(feature = "no-libc")]
+    #[test]
+    fn test_write_std_handles_only() {
+        assert_eq!(unsafe { libc_write(crate::__LIBC_STDOUT, b"") }, Ok(0));
+        assert_eq!(unsafe { libc_write(7, b"x") }, Err(ERROR_INVALID_HANDLE));
+    }
+}
@@ -0,0 +1,239 @@
+use core::fmt::Write;
+
+/// Size of the buffer holding the word currently being wrapped. Words longer than this are
+/// hard-broken even if they would fit in the requested width.
+const WRAP_WORD_BUFFER: usize = 128;
+
+/// Macro for printing to the standard output, word-wrapped so that no line is longer than
+/// `width` columns.
+///
+/// Lines are broken at spaces where possible, and words longer than `width` are split across
+/// lines. Runs of whitespace between words are collapsed into a single space. East Asian wide
+/// characters, such as CJK ideographs, count as the two columns they take up on a terminal.
+///
+/// ```rust
+/// # use libc_print::libc_wrapln;
+/// libc_wrapln!(20, "The quick brown fox jumps over the lazy dog {} times", 3);
+/// ```
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_wrapln {
+    ($width:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCWrapWriter::new(
+                    $crate::__LibCBufWriter::new($crate::__LIBC_STDOUT),
+                    $width,
+                );
+                $crate::__libc_line_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.finish();
+            }
+        }
+    };
+}
+
+/// Macro for printing to the standard error, word-wrapped so that no line is longer than
+/// `width` columns.
+///
+/// See [`libc_wrapln`] for details.
+///
+/// Does not panic on failure to write - instead silently ignores errors.
+#[macro_export]
+macro_rules! libc_ewrapln {
+    ($width:expr, $($arg:tt)*) => {
+        {
+            #[allow(unused_must_use)]
+            {
+                let mut stm = $crate::__LibCWrapWriter::new(
+                    $crate::__LibCBufWriter::new($crate::__LIBC_STDERR),
+                    $width,
+                );
+                $crate::__libc_line_prefix(&mut stm);
+                stm.write_fmt(format_args!($($arg)*));
+                stm.finish();
+            }
+        }
+    };
+}
+
+/// A `core::fmt::Write` adapter that word-wraps its output.
+///
+/// The current word is held back in a small buffer until we know whether it fits on the
+/// current line.
+#[doc(hidden)]
+pub struct __LibCWrapWriter<W: Write> {
+    inner: W,
+    width: usize,
+    /// Columns already emitted on the current line
+    col: usize,
+    word: [u8; WRAP_WORD_BUFFER],
+    word_len: usize,
+    word_cols: usize,
+    /// The buffered word continues a word that was hard-broken, so must not be preceded by a
+    /// space
+    continuation: bool,
+}
+
+impl<W: Write> Write for __LibCWrapWriter<W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.finish_word()?;
+                    self.continuation = false;
+                }
+                '\n' => {
+                    self.finish_word()?;
+                    self.newline()?;
+                    self.continuation = false;
+                }
+                c => {
+                    let cols = char_width(c);
+                    if self.word_cols > 0 && self.word_cols + cols > self.width
+                        || self.word_len + c.len_utf8() > WRAP_WORD_BUFFER
+                    {
+                        self.finish_word()?;
+                        self.continuation = true;
+                    }
+                    c.encode_utf8(&mut self.word[self.word_len..]);
+                    self.word_len += c.len_utf8();
+                    self.word_cols += cols;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> __LibCWrapWriter<W> {
+    #[inline]
+    pub fn new(inner: W, width: usize) -> Self {
+        __LibCWrapWriter {
+            inner,
+            width: width.max(1),
+            col: 0,
+            word: [0; WRAP_WORD_BUFFER],
+            word_len: 0,
+            word_cols: 0,
+            continuation: false,
+        }
+    }
+
+    #[inline]
+    pub fn write_fmt(&mut self, args: core::fmt::Arguments) -> core::fmt::Result {
+        Write::write_fmt(self, args)
+    }
+
+    /// Flushes the last word and terminates the final line.
+    pub fn finish(&mut self) -> core::fmt::Result {
+        self.finish_word()?;
+        self.newline()
+    }
+
+    fn newline(&mut self) -> core::fmt::Result {
+        self.col = 0;
+        self.inner.write_str(crate::__LIBC_NEWLINE)
+    }
+
+    /// Places the buffered word on the current line, or the next one if it doesn't fit.
+    fn finish_word(&mut self) -> core::fmt::Result {
+        if self.word_len == 0 {
+            return Ok(());
+        }
+
+        let mut sep = self.col > 0 && !self.continuation;
+        if self.col > 0 && self.col + sep as usize + self.word_cols > self.width {
+            self.newline()?;
+            sep = false;
+        }
+        if sep {
+            self.inner.write_str(" ")?;
+            self.col += 1;
+        }
+
+        // SAFETY: the buffer only ever contains whole UTF-8 encoded chars
+        let word = unsafe { core::str::from_utf8_unchecked(&self.word[..self.word_len]) };
+        self.inner.write_str(word)?;
+        self.col += self.word_cols;
+        self.word_len = 0;
+        self.word_cols = 0;
+        Ok(())
+    }
+}
+
+/// Returns the number of terminal columns `c` takes up: two for East Asian wide and fullwidth
+/// characters, and one for everything else.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    fn wrap(width: usize, s: &str) -> String {
+        let mut w = __LibCWrapWriter::new(String::new(), width);
+        w.write_str(s).unwrap();
+        w.finish().unwrap();
+        w.inner
+    }
+
+    /// Returns `expected` with its `\n`s replaced by the line ending the writer uses.
+    fn lines(expected: &str) -> String {
+        expected.replace('\n', crate::__LIBC_NEWLINE)
+    }
+
+    #[test]
+    fn test_wrap_words() {
+        assert_eq!(
+            wrap(10, "the quick brown fox"),
+            lines("the quick\nbrown fox\n")
+        );
+        assert_eq!(wrap(10, "  spaced   out  "), lines("spaced out\n"));
+        assert_eq!(
+            wrap(10, "one\ntwo three four"),
+            lines("one\ntwo three\nfour\n")
+        );
+        assert_eq!(
+            wrap(10, "日本語 日本語 日本語"),
+            lines("日本語\n日本語\n日本語\n")
+        );
+        assert_eq!(wrap(13, "日本語 日本語"), lines("日本語 日本語\n"));
+    }
+
+    #[test]
+    fn test_wrap_long_words() {
+        assert_eq!(wrap(4, "abcdefghij"), lines("abcd\nefgh\nij\n"));
+        assert_eq!(wrap(4, "ab abcdefg"), lines("ab\nabcd\nefg\n"));
+        assert_eq!(wrap(0, "ab"), lines("a\nb\n"));
+        assert_eq!(wrap(5, "日本語"), lines("日本\n語\n"));
+        // A wide character that can't fit is still placed alone on a line
+        assert_eq!(wrap(1, "日本"), lines("日\n本\n"));
+    }
+
+    #[test]
+    fn test_wrapln() {
+        libc_wrapln!(20, "The quick brown fox jumps {} times", 3);
+        libc_ewrapln!(20, "The quick brown fox jumps over the lazy dog");
+    }
+}